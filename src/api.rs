@@ -0,0 +1,179 @@
+use std::{convert::Infallible, net::{IpAddr, SocketAddr}};
+
+use hyper::{header, service::{make_service_fn, service_fn}, Body, Method, Request, Response, Server, StatusCode};
+use serde::Serialize;
+use tokio::sync::watch;
+
+use crate::state::{ForwardStatus, ForwardedPort};
+
+/// A forward as reported by the status API
+#[derive(Clone, PartialEq, Eq, Serialize)]
+pub struct ForwardSummary {
+    pub namespace: String,
+    pub service: String,
+    pub pod: Option<String>,
+    pub pod_name: Option<String>,
+    pub node_name: Option<String>,
+    pub remote_port: u16,
+    pub local_port: u16,
+    pub local_address: String,
+    pub status: &'static str,
+    pub error: Option<String>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub reconnects: u64,
+}
+
+impl From<&ForwardedPort> for ForwardSummary {
+    fn from(fw_port: &ForwardedPort) -> ForwardSummary {
+        let (status, error) = match &fw_port.status {
+            ForwardStatus::Connecting => ("connecting", None),
+            ForwardStatus::Active => ("active", None),
+            ForwardStatus::Failed(reason) => ("failed", Some(reason.clone())),
+        };
+        ForwardSummary {
+            namespace: fw_port.namespace.clone(),
+            service: fw_port.service.clone(),
+            pod: fw_port.pod.clone(),
+            pod_name: fw_port.pod_name.clone(),
+            node_name: fw_port.node_name.clone(),
+            remote_port: fw_port.port,
+            local_port: fw_port.local_port,
+            local_address: fw_port.local_address(),
+            status,
+            error,
+            bytes_sent: fw_port.traffic.sent(),
+            bytes_received: fw_port.traffic.received(),
+            reconnects: fw_port.reconnects,
+        }
+    }
+}
+
+/// Serves the forwards published by `AppState` as JSON on `GET /forwards`, on the `loopback` address only.
+pub fn spawn(loopback: IpAddr, port: u16, forwards: watch::Receiver<Vec<ForwardSummary>>) -> Result<(), hyper::Error> {
+    serve(loopback, port, forwards, handle_request)
+}
+
+/// Serves metrics of the forwards published by `AppState` in the Prometheus text format on `GET /metrics`, on the `loopback` address only.
+pub fn spawn_metrics(loopback: IpAddr, port: u16, forwards: watch::Receiver<Vec<ForwardSummary>>) -> Result<(), hyper::Error> {
+    serve(loopback, port, forwards, handle_metrics_request)
+}
+
+type Handler = fn(Request<Body>, &watch::Receiver<Vec<ForwardSummary>>) -> Response<Body>;
+
+fn serve(loopback: IpAddr, port: u16, forwards: watch::Receiver<Vec<ForwardSummary>>, handler: Handler) -> Result<(), hyper::Error> {
+    let addr = SocketAddr::new(loopback, port);
+    let builder = Server::try_bind(&addr)?;
+    let make_service = make_service_fn(move |_conn| {
+        let forwards = forwards.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let response = handler(req, &forwards);
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+    tokio::spawn(builder.serve(make_service));
+    Ok(())
+}
+
+fn handle_request(req: Request<Body>, forwards: &watch::Receiver<Vec<ForwardSummary>>) -> Response<Body> {
+    let mut response = Response::default();
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, "/forwards") => {
+            let body = serde_json::to_string(&*forwards.borrow()).unwrap_or_else(|_| "[]".to_string());
+            response.headers_mut().insert(header::CONTENT_TYPE, header::HeaderValue::from_static("application/json"));
+            *response.body_mut() = Body::from(body);
+        }
+        (_, "/forwards") => *response.status_mut() = StatusCode::METHOD_NOT_ALLOWED,
+        _ => *response.status_mut() = StatusCode::NOT_FOUND,
+    }
+    response
+}
+
+fn handle_metrics_request(req: Request<Body>, forwards: &watch::Receiver<Vec<ForwardSummary>>) -> Response<Body> {
+    let mut response = Response::default();
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => {
+            response.headers_mut().insert(header::CONTENT_TYPE, header::HeaderValue::from_static("text/plain; version=0.0.4"));
+            *response.body_mut() = Body::from(render_metrics(&forwards.borrow()));
+        }
+        (_, "/metrics") => *response.status_mut() = StatusCode::METHOD_NOT_ALLOWED,
+        _ => *response.status_mut() = StatusCode::NOT_FOUND,
+    }
+    response
+}
+
+/// The counters are those of the forwards running, starting over when a forward is started again.
+fn render_metrics(forwards: &[ForwardSummary]) -> String {
+    // Namespaces and services are DNS names, which need no escaping as label values
+    let labels = |forward: &ForwardSummary| format!(
+        "namespace=\"{}\",service=\"{}\",remote_port=\"{}\",local_port=\"{}\"",
+        forward.namespace, forward.service, forward.remote_port, forward.local_port
+    );
+    let active = forwards.iter().filter(|forward| forward.status == "active").count();
+    let mut metrics = vec![
+        "# HELP portnord_forwards_active Forwards connected to their pod.".to_string(),
+        "# TYPE portnord_forwards_active gauge".to_string(),
+        format!("portnord_forwards_active {}", active),
+        "# HELP portnord_forward_bytes_total Bytes proxied by a forward, to the pod (sent) or from it (received).".to_string(),
+        "# TYPE portnord_forward_bytes_total counter".to_string(),
+    ];
+    for forward in forwards {
+        metrics.push(format!("portnord_forward_bytes_total{{{},direction=\"sent\"}} {}", labels(forward), forward.bytes_sent));
+        metrics.push(format!("portnord_forward_bytes_total{{{},direction=\"received\"}} {}", labels(forward), forward.bytes_received));
+    }
+    metrics.push("# HELP portnord_forward_reconnects_total Times a forward reconnected after losing its connection.".to_string());
+    metrics.push("# TYPE portnord_forward_reconnects_total counter".to_string());
+    for forward in forwards {
+        metrics.push(format!("portnord_forward_reconnects_total{{{}}} {}", labels(forward), forward.reconnects));
+    }
+    metrics.push(String::new());
+    metrics.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(service: &str, status: &'static str, bytes_sent: u64, bytes_received: u64, reconnects: u64) -> ForwardSummary {
+        ForwardSummary {
+            namespace: "default".to_string(),
+            service: service.to_string(),
+            pod: None,
+            pod_name: None,
+            node_name: None,
+            remote_port: 80,
+            local_port: 8080,
+            local_address: "127.0.0.1:8080".to_string(),
+            status,
+            error: None,
+            bytes_sent,
+            bytes_received,
+            reconnects,
+        }
+    }
+
+    #[test]
+    fn metrics_without_forwards_only_have_the_gauge() {
+        let metrics = render_metrics(&[]);
+        assert!(metrics.contains("\nportnord_forwards_active 0\n"));
+        assert!(!metrics.contains("portnord_forward_bytes_total{"));
+        assert!(metrics.ends_with('\n'));
+    }
+
+    #[test]
+    fn metrics_count_each_forward() {
+        let metrics = render_metrics(&[summary("web", "active", 10, 20, 0), summary("api", "failed", 0, 0, 3)]);
+        let lines: Vec<&str> = metrics.lines().filter(|line| !line.starts_with('#')).collect();
+        assert_eq!(lines, [
+            "portnord_forwards_active 1",
+            "portnord_forward_bytes_total{namespace=\"default\",service=\"web\",remote_port=\"80\",local_port=\"8080\",direction=\"sent\"} 10",
+            "portnord_forward_bytes_total{namespace=\"default\",service=\"web\",remote_port=\"80\",local_port=\"8080\",direction=\"received\"} 20",
+            "portnord_forward_bytes_total{namespace=\"default\",service=\"api\",remote_port=\"80\",local_port=\"8080\",direction=\"sent\"} 0",
+            "portnord_forward_bytes_total{namespace=\"default\",service=\"api\",remote_port=\"80\",local_port=\"8080\",direction=\"received\"} 0",
+            "portnord_forward_reconnects_total{namespace=\"default\",service=\"web\",remote_port=\"80\",local_port=\"8080\"} 0",
+            "portnord_forward_reconnects_total{namespace=\"default\",service=\"api\",remote_port=\"80\",local_port=\"8080\"} 3",
+        ]);
+    }
+}
@@ -1,15 +1,287 @@
-use clap::Parser;
+use std::{ffi::OsString, net::IpAddr, path::PathBuf, str::FromStr};
+
+use clap::{error::ErrorKind, parser::ValueSource, CommandFactory, FromArgMatches, Parser, ValueEnum};
+
+use crate::config;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 pub struct Args {
-    /// Point to a specific namespace ('default' otherwise)
+    /// Point to a specific namespace, repeat to list services across several ('default' otherwise)
     #[clap(short, long)]
-    pub namespace: Option<String>
+    pub namespace: Vec<String>,
+
+    /// List services across all namespaces
+    #[clap(short = 'A', long, conflicts_with = "namespace")]
+    pub all_namespaces: bool,
+
+    /// Only list the services of this type, repeat to list several (every type otherwise)
+    #[clap(long, value_name = "TYPE", value_parser = ["ClusterIP", "NodePort", "LoadBalancer", "ExternalName"])]
+    pub service_type: Vec<String>,
+
+    /// Use a specific kube context (the current context otherwise)
+    #[clap(short = 'c', long)]
+    pub context: Option<String>,
+
+    /// Read the kube config from this file rather than from $KUBECONFIG or ~/.kube/config
+    #[clap(long)]
+    pub kubeconfig: Option<PathBuf>,
+
+    /// Read the defaults of the flags from this TOML file rather than from ~/.config/portnord/config.toml
+    #[clap(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Seconds between refreshes of the services list
+    #[clap(long, default_value_t = 10)]
+    pub refresh_interval: u64,
+
+    /// Times to retry listing the services at startup, waiting twice as long each time, before waiting for the next refresh (giving up with --list)
+    #[clap(long, default_value_t = 3)]
+    pub startup_retries: u32,
+
+    /// Attempts to reconnect a forward whose connection was lost
+    #[clap(long, default_value_t = 5)]
+    pub max_retries: u32,
+
+    /// Seconds to wait for a forward to connect to its pod before giving up
+    #[clap(long, value_name = "SECS", default_value_t = 10)]
+    pub connect_timeout: u64,
+
+    /// Seconds given to the requests and connections in flight through a forward to complete once stopped or on quit
+    #[clap(long, value_name = "SECS", default_value_t = 5)]
+    pub shutdown_timeout: u64,
+
+    /// Local address to bind forwarded ports to, IPv4 or IPv6 like ::1, falling back to 127.0.0.1 when malformed
+    #[clap(long, default_value = "127.0.0.1")]
+    pub bind_address: String,
+
+    /// How forwarded connections are relayed to the pods
+    #[clap(long, value_enum, default_value_t = ProxyMode::Tcp)]
+    pub proxy_mode: ProxyMode,
+
+    /// Re-establish the forwards that were active when the previous session was quit
+    #[clap(long)]
+    pub restore: bool,
+
+    /// Record forwards in the UI without connecting to the pods nor binding local ports
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Forward all the ports of a service without asking for confirmation first
+    #[clap(long)]
+    pub no_confirm: bool,
+
+    /// List the OpenShift routes of the namespaces (w) to forward the service port behind them
+    #[clap(long)]
+    pub include_routes: bool,
+
+    /// Forward a service port at launch, optionally to a different local port (repeatable); qualify the service as NAMESPACE/SERVICE when several namespaces have one of that name
+    #[clap(short, long = "forward", value_name = "SERVICE:REMOTE[:LOCAL]")]
+    pub forwards: Vec<ForwardSpec>,
+
+    /// Forward --port of every service matching this label selector at launch, e.g. app=frontend
+    #[clap(short = 'l', long, requires = "port")]
+    pub selector: Option<String>,
+
+    /// Port to forward on each service matching --selector, to the next free local port when taken
+    #[clap(long, requires = "selector")]
+    pub port: Option<u16>,
+
+    /// Serve the forwarded ports as JSON on http://127.0.0.1:<API_PORT>/forwards, or [::1] with an IPv6 --bind-address
+    #[clap(long)]
+    pub api_port: Option<u16>,
+
+    /// Serve Prometheus metrics of the forwards on http://127.0.0.1:<METRICS_PORT>/metrics, or [::1] with an IPv6 --bind-address
+    #[clap(long)]
+    pub metrics_port: Option<u16>,
+
+    /// Append the status messages and forward events to this file
+    #[clap(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Milliseconds to wait for input before checking for updates again
+    #[clap(long, value_name = "MS", default_value_t = 250)]
+    pub tick_rate: u64,
+
+    /// Print the services and their ports, then exit without starting the UI
+    #[clap(long)]
+    pub list: bool,
+
+    /// Print the version of the cluster and the namespace targeted, then exit without starting the UI
+    #[clap(long, conflicts_with = "list")]
+    pub cluster_info: bool,
+
+    /// Format of the --list output
+    #[clap(long, value_enum, default_value_t = OutputFormat::Table, requires = "list")]
+    pub output: OutputFormat,
+
+    /// Key bindings to use for navigation
+    #[clap(long, value_enum, default_value_t = Keymap::Default)]
+    pub keymap: Keymap,
+
+    /// Colors to draw the UI with
+    #[clap(long, value_enum, default_value_t = ThemePreset::Dark)]
+    pub theme: ThemePreset,
+
+    /// Use the denser layout of small terminals whatever the size of the terminal
+    #[clap(long)]
+    pub compact: bool,
+
+    /// Ignore the keys changing the forwards and ask for a second q to quit, e.g. to demo on a shared screen
+    #[clap(long)]
+    pub read_only: bool,
+
+    /// Print errors to stderr only, and nothing but the requested output to stdout, e.g. when scripting --list
+    #[clap(short, long)]
+    pub quiet: bool
+}
+
+/// Parses an IPv4 or IPv6 address, the latter with or without brackets (e.g. `[::1]`).
+pub fn parse_bind_address(text: &str) -> Result<IpAddr, String> {
+    let unbracketed = text.strip_prefix('[').and_then(|text| text.strip_suffix(']')).unwrap_or(text);
+    IpAddr::from_str(unbracketed).map_err(|_| format!("'{}' is neither an IPv4 nor an IPv6 address", text))
+}
+
+#[derive(Clone, Debug)]
+pub struct ForwardSpec {
+    pub service: String,
+    pub remote_port: u16,
+    pub local_port: Option<u16>,
+}
+
+impl FromStr for ForwardSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<ForwardSpec, String> {
+        let parse_port = |port: &str| port.parse::<u16>().map_err(|_| format!("'{}' is not a valid port", port));
+        match spec.split(':').collect::<Vec<&str>>()[..] {
+            [service, remote_port] if !service.is_empty() => Ok(ForwardSpec {
+                service: service.to_owned(),
+                remote_port: parse_port(remote_port)?,
+                local_port: None,
+            }),
+            [service, remote_port, local_port] if !service.is_empty() => Ok(ForwardSpec {
+                service: service.to_owned(),
+                remote_port: parse_port(remote_port)?,
+                local_port: Some(parse_port(local_port)?),
+            }),
+            _ => Err("expected SERVICE:REMOTE[:LOCAL]".to_string()),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProxyMode {
+    /// Pipe the bytes of each connection as is, whatever the protocol
+    Tcp,
+    /// Proxy HTTP/1 requests, counting the bytes as they stream
+    Http,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Keymap {
+    /// Arrow keys only
+    Default,
+    /// Arrow keys and hjkl
+    Vim,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThemePreset {
+    /// Colors for dark terminal backgrounds
+    Dark,
+    /// Colors for light terminal backgrounds
+    Light,
+    /// No colors, only bold, dim and reversed text
+    Mono,
 }
 
 impl Args {
+    /// Parses the command line, the flags it leaves out taking their value from the config file when it has one.
     pub fn collect() -> Args {
-        Args::parse()
+        let command_line: Vec<OsString> = std::env::args_os().collect();
+        let mut command = Args::command();
+        let matches = command.clone().get_matches_from(&command_line);
+        let (path, table) = match config::load(matches.get_one::<PathBuf>("config").map(PathBuf::as_path)) {
+            Ok(Some(config)) => config,
+            Ok(None) => return Args::from_arg_matches(&matches).unwrap_or_else(|error| error.exit()),
+            Err(text) => command.error(ErrorKind::Io, text).exit(),
+        };
+        let on_command_line: Vec<&clap::Arg> = command
+            .get_arguments()
+            .filter(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine))
+            .collect();
+        let mut config_args: Vec<OsString> = vec![];
+        for (key, value) in &table {
+            // Keyed by the long flag, with underscores for dashes, e.g. `bind_address` for --bind-address, or by field name
+            let arg = command
+                .get_arguments()
+                .filter(|arg| arg.get_long().is_some_and(|long| !["config", "help", "version"].contains(&long)))
+                .find(|arg| arg.get_long().is_some_and(|long| long.replace('-', "_") == *key) || arg.get_id() == key.as_str());
+            let arg = match arg {
+                Some(arg) => arg,
+                None => command.clone().error(ErrorKind::UnknownArgument, format!("Unknown key '{}' in the config file {}", key, path.display())).exit(),
+            };
+            // A flag set on the command line also overrides the keys of the file it conflicts with
+            let overridden = on_command_line
+                .iter()
+                .any(|cli_arg| cli_arg.get_id() == arg.get_id() || command.get_arg_conflicts_with(cli_arg).contains(&arg));
+            if overridden {
+                continue;
+            }
+            let flag = format!("--{}", arg.get_long().unwrap_or_default());
+            match config::to_args(&flag, value) {
+                Ok(args) => config_args.extend(args.into_iter().map(OsString::from)),
+                Err(text) => command.clone().error(ErrorKind::InvalidValue, format!("Invalid key '{}' in the config file {}: {}", key, path.display(), text)).exit(),
+            }
+        }
+        // Given first, the arguments of the file are parsed like those of the command line
+        let args = command_line.iter().take(1).chain(&config_args).chain(command_line.iter().skip(1));
+        match Args::try_parse_from(args) {
+            Ok(args) => args,
+            Err(error) => {
+                // Only the first paragraph of the error, the usage being appended again
+                let rendered = error.render().to_string();
+                let reason = rendered.trim_start_matches("error: ").split("\n\n").next().unwrap_or_default().to_owned();
+                command.error(error.kind(), format!("Invalid config file {} ({}): {}", path.display(), config::PRECEDENCE, reason)).exit()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forward_spec_takes_an_optional_local_port() {
+        let spec = ForwardSpec::from_str("web:80").unwrap();
+        assert_eq!((spec.service.as_str(), spec.remote_port, spec.local_port), ("web", 80, None));
+        let spec = ForwardSpec::from_str("web:80:8080").unwrap();
+        assert_eq!((spec.service.as_str(), spec.remote_port, spec.local_port), ("web", 80, Some(8080)));
+    }
+
+    #[test]
+    fn malformed_forward_spec_is_refused() {
+        assert_eq!(ForwardSpec::from_str("web:http").unwrap_err(), "'http' is not a valid port");
+        assert_eq!(ForwardSpec::from_str("web:80:70000").unwrap_err(), "'70000' is not a valid port");
+        for spec in ["web", ":80", "web:80:8080:1", ""] {
+            assert_eq!(ForwardSpec::from_str(spec).unwrap_err(), "expected SERVICE:REMOTE[:LOCAL]");
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn bind_address_may_be_bracketed() {
+        assert_eq!(parse_bind_address("0.0.0.0"), Ok(IpAddr::from([0, 0, 0, 0])));
+        assert_eq!(parse_bind_address("::1"), Ok(IpAddr::from([0, 0, 0, 0, 0, 0, 0, 1])));
+        assert_eq!(parse_bind_address("[::1]"), Ok(IpAddr::from([0, 0, 0, 0, 0, 0, 0, 1])));
+        assert_eq!(parse_bind_address("localhost"), Err("'localhost' is neither an IPv4 nor an IPv6 address".to_string()));
+        assert!(parse_bind_address("[127.0.0.1").is_err());
+    }
+}
@@ -0,0 +1,319 @@
+use futures::future::BoxFuture;
+use k8s_openapi::{api::core::v1::{Endpoints, Namespace, Pod, Service}, apimachinery::pkg::version::Info};
+use kube::{Api, Client, api::{ApiResource, DynamicObject, GroupVersionKind, ListParams, Portforwarder}};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// What portnord asks of the cluster, the namespace `None` standing for every namespace when listing
+pub trait ClusterClient: Send + Sync {
+    /// Namespace of the context, listed from when no namespace is given.
+    fn default_namespace(&self) -> &str;
+
+    fn server_version(&self) -> BoxFuture<'_, Result<Info, kube::Error>>;
+
+    fn list_namespaces(&self) -> BoxFuture<'_, Result<Vec<Namespace>, kube::Error>>;
+
+    fn get_namespace<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Namespace, kube::Error>>;
+
+    /// Lists the services, only those matching the label selector `labels` when given.
+    fn list_services<'a>(&'a self, namespace: Option<&'a str>, labels: Option<&'a str>) -> BoxFuture<'a, Result<Vec<Service>, kube::Error>>;
+
+    /// Lists the endpoints, which carry the labels of their service.
+    fn list_endpoints<'a>(&'a self, namespace: Option<&'a str>, labels: Option<&'a str>) -> BoxFuture<'a, Result<Vec<Endpoints>, kube::Error>>;
+
+    /// Lists the pods of `namespace`, only those matching the label selector `labels` when given.
+    fn list_pods<'a>(&'a self, namespace: &'a str, labels: Option<&'a str>) -> BoxFuture<'a, Result<Vec<Pod>, kube::Error>>;
+
+    fn get_pod<'a>(&'a self, namespace: &'a str, name: &'a str) -> BoxFuture<'a, Result<Pod, kube::Error>>;
+
+    /// Lists the OpenShift routes, failing with a 404 on clusters without them.
+    fn list_routes<'a>(&'a self, namespace: Option<&'a str>) -> BoxFuture<'a, Result<Vec<DynamicObject>, kube::Error>>;
+
+    fn port_forward<'a>(&'a self, namespace: &'a str, pod: &'a str, port: u16) -> BoxFuture<'a, Result<Box<dyn PortForward>, kube::Error>>;
+}
+
+/// A port forward to a pod, like kube's `Portforwarder`, which can't be built outside of kube
+pub trait PortForward: Send {
+    /// Takes the stream of `port`, `None` when the pod didn't open it or it was already taken.
+    fn take_stream(&mut self, port: u16) -> Option<Box<dyn PortStream>>;
+
+    /// Takes a future resolving with the error reported on `port`, if any, once the forward ends.
+    fn take_error(&mut self, port: u16) -> Option<BoxFuture<'static, Option<String>>>;
+
+    fn abort(&self);
+}
+
+/// Bytes relayed to a port of a pod
+pub trait PortStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> PortStream for T {}
+
+impl PortForward for Portforwarder {
+    fn take_stream(&mut self, port: u16) -> Option<Box<dyn PortStream>> {
+        Portforwarder::take_stream(self, port).map(|stream| Box::new(stream) as Box<dyn PortStream>)
+    }
+
+    fn take_error(&mut self, port: u16) -> Option<BoxFuture<'static, Option<String>>> {
+        Portforwarder::take_error(self, port).map(|error| Box::pin(error) as BoxFuture<'static, Option<String>>)
+    }
+
+    fn abort(&self) {
+        Portforwarder::abort(self)
+    }
+}
+
+/// The cluster of a kube config, reached through its API server
+pub struct KubeClient {
+    client: Client,
+    default_namespace: String,
+}
+
+impl KubeClient {
+    pub fn new(client: Client, default_namespace: String) -> KubeClient {
+        KubeClient { client, default_namespace }
+    }
+
+    fn api<K>(&self, namespace: Option<&str>) -> Api<K>
+    where
+        K: kube::Resource,
+        K::DynamicType: Default,
+    {
+        match namespace {
+            Some(namespace) => Api::namespaced(self.client.clone(), namespace),
+            None => Api::all(self.client.clone()),
+        }
+    }
+}
+
+/// Parameters to list the objects matching the label selector `labels`, every object when `None`.
+fn list_params(labels: Option<&str>) -> ListParams {
+    match labels {
+        Some(labels) => ListParams::default().labels(labels),
+        None => ListParams::default(),
+    }
+}
+
+impl ClusterClient for KubeClient {
+    fn default_namespace(&self) -> &str {
+        &self.default_namespace
+    }
+
+    fn server_version(&self) -> BoxFuture<'_, Result<Info, kube::Error>> {
+        Box::pin(self.client.apiserver_version())
+    }
+
+    fn list_namespaces(&self) -> BoxFuture<'_, Result<Vec<Namespace>, kube::Error>> {
+        Box::pin(async move { Ok(self.api::<Namespace>(None).list(&ListParams::default()).await?.items) })
+    }
+
+    fn get_namespace<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Namespace, kube::Error>> {
+        Box::pin(async move { self.api::<Namespace>(None).get(name).await })
+    }
+
+    fn list_services<'a>(&'a self, namespace: Option<&'a str>, labels: Option<&'a str>) -> BoxFuture<'a, Result<Vec<Service>, kube::Error>> {
+        Box::pin(async move { Ok(self.api::<Service>(namespace).list(&list_params(labels)).await?.items) })
+    }
+
+    fn list_endpoints<'a>(&'a self, namespace: Option<&'a str>, labels: Option<&'a str>) -> BoxFuture<'a, Result<Vec<Endpoints>, kube::Error>> {
+        Box::pin(async move { Ok(self.api::<Endpoints>(namespace).list(&list_params(labels)).await?.items) })
+    }
+
+    fn list_pods<'a>(&'a self, namespace: &'a str, labels: Option<&'a str>) -> BoxFuture<'a, Result<Vec<Pod>, kube::Error>> {
+        Box::pin(async move { Ok(self.api::<Pod>(Some(namespace)).list(&list_params(labels)).await?.items) })
+    }
+
+    fn get_pod<'a>(&'a self, namespace: &'a str, name: &'a str) -> BoxFuture<'a, Result<Pod, kube::Error>> {
+        Box::pin(async move { self.api::<Pod>(Some(namespace)).get(name).await })
+    }
+
+    fn list_routes<'a>(&'a self, namespace: Option<&'a str>) -> BoxFuture<'a, Result<Vec<DynamicObject>, kube::Error>> {
+        Box::pin(async move {
+            let resource = ApiResource::from_gvk(&GroupVersionKind::gvk("route.openshift.io", "v1", "Route"));
+            let route_api: Api<DynamicObject> = match namespace {
+                Some(namespace) => Api::namespaced_with(self.client.clone(), namespace, &resource),
+                None => Api::all_with(self.client.clone(), &resource),
+            };
+            Ok(route_api.list(&ListParams::default()).await?.items)
+        })
+    }
+
+    fn port_forward<'a>(&'a self, namespace: &'a str, pod: &'a str, port: u16) -> BoxFuture<'a, Result<Box<dyn PortForward>, kube::Error>> {
+        Box::pin(async move {
+            let port_forwarder = self.api::<Pod>(Some(namespace)).portforward(pod, &[port]).await?;
+            Ok(Box::new(port_forwarder) as Box<dyn PortForward>)
+        })
+    }
+}
+
+/// A cluster of canned objects, for the tests to run without an API server
+#[cfg(test)]
+pub mod fake {
+    use std::{collections::{BTreeMap, BTreeSet}, sync::{Arc, atomic::{AtomicUsize, Ordering}}};
+
+    use futures::future::BoxFuture;
+    use k8s_openapi::{
+        api::core::v1::{Container, ContainerPort, Endpoints, Namespace, Pod, PodCondition, PodSpec, PodStatus, Service, ServicePort, ServiceSpec},
+        apimachinery::pkg::{apis::meta::v1::ObjectMeta, util::intstr::IntOrString, version::Info},
+    };
+    use kube::{api::DynamicObject, core::ErrorResponse, ResourceExt};
+    use tokio::io::DuplexStream;
+
+    use super::{ClusterClient, PortForward, PortStream};
+
+    #[derive(Default)]
+    pub struct FakeCluster {
+        pub services: Vec<Service>,
+        pub pods: Vec<Pod>,
+        /// Port the pods open when forwarded to, whichever was asked for; port forwards are refused when `None`
+        pub opened_port: Option<u16>,
+        /// Number of port forwards aborted so far
+        pub aborted_forwards: Arc<AtomicUsize>,
+    }
+
+    /// Port forward opening `port` only, its stream never sending anything.
+    struct FakePortForward {
+        port: u16,
+        stream: Option<DuplexStream>,
+        aborted_forwards: Arc<AtomicUsize>,
+    }
+
+    impl PortForward for FakePortForward {
+        fn take_stream(&mut self, port: u16) -> Option<Box<dyn PortStream>> {
+            match port == self.port {
+                true => self.stream.take().map(|stream| Box::new(stream) as Box<dyn PortStream>),
+                false => None,
+            }
+        }
+
+        fn take_error(&mut self, _port: u16) -> Option<BoxFuture<'static, Option<String>>> {
+            None
+        }
+
+        fn abort(&self) {
+            self.aborted_forwards.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Fails like the API server would on a missing object.
+    fn not_found(kind: &str, name: &str) -> kube::Error {
+        kube::Error::Api(ErrorResponse { status: "Failure".to_string(), message: format!("{} \"{}\" not found", kind, name), reason: "NotFound".to_string(), code: 404 })
+    }
+
+    /// Whether `labels` has every `key=value` of the selector `selector`.
+    fn matches(labels: &BTreeMap<String, String>, selector: &str) -> bool {
+        selector
+            .split(',')
+            .filter_map(|term| term.split_once('='))
+            .all(|(key, value)| labels.get(key).map(String::as_str) == Some(value))
+    }
+
+    fn in_scope<K: ResourceExt>(object: &K, namespace: Option<&str>, labels: Option<&str>) -> bool {
+        namespace.is_none_or(|namespace| object.namespace().as_deref() == Some(namespace))
+            && labels.is_none_or(|labels| matches(object.labels(), labels))
+    }
+
+    impl ClusterClient for FakeCluster {
+        fn default_namespace(&self) -> &str {
+            "default"
+        }
+
+        fn server_version(&self) -> BoxFuture<'_, Result<Info, kube::Error>> {
+            Box::pin(async { Ok(Info { git_version: "v1.24.0".to_string(), ..Info::default() }) })
+        }
+
+        fn list_namespaces(&self) -> BoxFuture<'_, Result<Vec<Namespace>, kube::Error>> {
+            let names: BTreeSet<String> = self.services.iter().filter_map(ResourceExt::namespace).collect();
+            let namespaces = names.into_iter().map(|name| Namespace { metadata: ObjectMeta { name: Some(name), ..ObjectMeta::default() }, ..Namespace::default() }).collect();
+            Box::pin(async { Ok(namespaces) })
+        }
+
+        fn get_namespace<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Namespace, kube::Error>> {
+            Box::pin(async move {
+                match self.services.iter().any(|svc| svc.namespace().as_deref() == Some(name)) {
+                    true => Ok(Namespace { metadata: ObjectMeta { name: Some(name.to_owned()), ..ObjectMeta::default() }, ..Namespace::default() }),
+                    false => Err(not_found("namespaces", name)),
+                }
+            })
+        }
+
+        fn list_services<'a>(&'a self, namespace: Option<&'a str>, labels: Option<&'a str>) -> BoxFuture<'a, Result<Vec<Service>, kube::Error>> {
+            Box::pin(async move { Ok(self.services.iter().filter(|svc| in_scope(*svc, namespace, labels)).cloned().collect()) })
+        }
+
+        fn list_endpoints<'a>(&'a self, _namespace: Option<&'a str>, _labels: Option<&'a str>) -> BoxFuture<'a, Result<Vec<Endpoints>, kube::Error>> {
+            Box::pin(async { Ok(vec![]) })
+        }
+
+        fn list_pods<'a>(&'a self, namespace: &'a str, labels: Option<&'a str>) -> BoxFuture<'a, Result<Vec<Pod>, kube::Error>> {
+            Box::pin(async move { Ok(self.pods.iter().filter(|pod| in_scope(*pod, Some(namespace), labels)).cloned().collect()) })
+        }
+
+        fn get_pod<'a>(&'a self, namespace: &'a str, name: &'a str) -> BoxFuture<'a, Result<Pod, kube::Error>> {
+            Box::pin(async move {
+                self.pods
+                    .iter()
+                    .find(|pod| pod.namespace().as_deref() == Some(namespace) && pod.name() == name)
+                    .cloned()
+                    .ok_or_else(|| not_found("pods", name))
+            })
+        }
+
+        fn list_routes<'a>(&'a self, _namespace: Option<&'a str>) -> BoxFuture<'a, Result<Vec<DynamicObject>, kube::Error>> {
+            Box::pin(async { Err(not_found("routes", "")) })
+        }
+
+        fn port_forward<'a>(&'a self, _namespace: &'a str, pod: &'a str, _port: u16) -> BoxFuture<'a, Result<Box<dyn PortForward>, kube::Error>> {
+            Box::pin(async move {
+                let port = self.opened_port.ok_or_else(|| not_found("pods", pod))?;
+                // The other end is dropped, the pod hanging up right away
+                let (stream, _) = tokio::io::duplex(64);
+                Ok(Box::new(FakePortForward { port, stream: Some(stream), aborted_forwards: self.aborted_forwards.clone() }) as Box<dyn PortForward>)
+            })
+        }
+    }
+
+    /// A ClusterIP service selecting the pods labelled `app=<name>`, with the given ports.
+    pub fn service(namespace: &str, name: &str, ports: Vec<ServicePort>) -> Service {
+        Service {
+            metadata: ObjectMeta { name: Some(name.to_owned()), namespace: Some(namespace.to_owned()), ..ObjectMeta::default() },
+            spec: Some(ServiceSpec {
+                type_: Some("ClusterIP".to_string()),
+                cluster_ip: Some("10.0.0.1".to_string()),
+                selector: Some(BTreeMap::from([("app".to_string(), name.to_owned())])),
+                ports: Some(ports),
+                ..ServiceSpec::default()
+            }),
+            ..Service::default()
+        }
+    }
+
+    /// A TCP port of a service, sending its traffic to `target_port` when given, to the same port otherwise.
+    pub fn service_port(port: i32, target_port: Option<IntOrString>) -> ServicePort {
+        ServicePort { port, target_port, protocol: Some("TCP".to_string()), ..ServicePort::default() }
+    }
+
+    /// A running pod labelled `app=<app>`, ready or not, with the named container ports given.
+    pub fn pod(namespace: &str, name: &str, app: &str, ready: bool, ports: &[(&str, i32)]) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                name: Some(name.to_owned()),
+                namespace: Some(namespace.to_owned()),
+                labels: Some(BTreeMap::from([("app".to_string(), app.to_owned())])),
+                ..ObjectMeta::default()
+            },
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    name: "main".to_string(),
+                    ports: Some(ports.iter().map(|(name, port)| ContainerPort { name: Some(name.to_string()), container_port: *port, ..ContainerPort::default() }).collect()),
+                    ..Container::default()
+                }],
+                node_name: Some("node-1".to_string()),
+                ..PodSpec::default()
+            }),
+            status: Some(PodStatus {
+                phase: Some("Running".to_string()),
+                conditions: Some(vec![PodCondition { type_: "Ready".to_string(), status: if ready { "True" } else { "False" }.to_string(), ..PodCondition::default() }]),
+                ..PodStatus::default()
+            }),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::{fs, io, path::{Path, PathBuf}};
+
+use toml::{value::Table, Value};
+
+use crate::session;
+
+/// Told when the config file is invalid, the values being hard to trace back otherwise
+pub const PRECEDENCE: &str = "flags on the command line override the config file, which overrides the defaults";
+
+/// `config.toml` in the config directory, whose keys are the flags, e.g. `bind_address = "0.0.0.0"`.
+pub fn default_path() -> Option<PathBuf> {
+    session::config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// Reads the config file given with --config, or the default one, which may not exist.
+pub fn load(path_opt: Option<&Path>) -> Result<Option<(PathBuf, Table)>, String> {
+    let path = match path_opt.map(Path::to_owned).or_else(default_path) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound && path_opt.is_none() => return Ok(None),
+        Err(error) => return Err(format!("Unable to read the config file {}: {}", path.display(), error)),
+    };
+    let table = toml::from_str(&contents).map_err(|error| format!("Unable to parse the config file {} ({}): {}", path.display(), PRECEDENCE, error))?;
+    Ok(Some((path, table)))
+}
+
+/// Spells out the value of a key as the arguments of its flag: a flag alone for `true`, once per item for arrays.
+pub fn to_args(flag: &str, value: &Value) -> Result<Vec<String>, String> {
+    match value {
+        Value::Boolean(true) => Ok(vec![flag.to_owned()]),
+        Value::Boolean(false) => Ok(vec![]),
+        Value::Array(values) => Ok(values.iter().map(|value| to_args(flag, value)).collect::<Result<Vec<_>, _>>()?.concat()),
+        Value::String(value) => Ok(vec![flag.to_owned(), value.to_owned()]),
+        Value::Integer(value) => Ok(vec![flag.to_owned(), value.to_string()]),
+        Value::Float(value) => Ok(vec![flag.to_owned(), value.to_string()]),
+        Value::Datetime(_) | Value::Table(_) => Err(format!("{} expects a string, a number, a boolean or an array of those", flag)),
+    }
+}
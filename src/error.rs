@@ -0,0 +1,47 @@
+use std::{fmt, io};
+
+/// Errors of the UI loop, `run_app` exiting on the fatal ones only
+#[derive(Debug)]
+pub enum PortnordError {
+    Kube(kube::Error),
+    /// Drawing or reading events from the terminal failed
+    Terminal(io::Error),
+    /// The forwards couldn't be saved on quit
+    Session(io::Error),
+    /// A forward couldn't be started, for a reason already worded for the messages pane
+    Forward(String),
+}
+
+impl PortnordError {
+    /// Whether the session can't go on after this error.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, PortnordError::Terminal(_) | PortnordError::Session(_))
+    }
+}
+
+impl fmt::Display for PortnordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortnordError::Kube(error) => write!(f, "{}", error),
+            PortnordError::Terminal(error) => write!(f, "Terminal error: {}", error),
+            PortnordError::Session(error) => write!(f, "Unable to save the session: {}", error),
+            PortnordError::Forward(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+impl std::error::Error for PortnordError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PortnordError::Kube(error) => Some(error),
+            PortnordError::Terminal(error) | PortnordError::Session(error) => Some(error),
+            PortnordError::Forward(_) => None,
+        }
+    }
+}
+
+impl From<kube::Error> for PortnordError {
+    fn from(error: kube::Error) -> PortnordError {
+        PortnordError::Kube(error)
+    }
+}
@@ -1,39 +1,125 @@
+mod api;
 mod cli;
+mod cluster;
+mod config;
+mod error;
+mod output;
+mod session;
 mod state;
 mod ui;
 
+use std::{fs::OpenOptions, net::{IpAddr, Ipv4Addr, Ipv6Addr}, path::Path, sync::{Mutex, atomic::{AtomicBool, Ordering}}, time::Duration};
+
 use cli::Args;
-use state::AppState;
+use state::{AppState, ForwardOptions, LaunchForwards, StatusMessage};
 use ui::UI;
 
-async fn run_app<'a>(ui: &mut ui::UI<'a>) -> ! {
+async fn run_app(ui: &mut ui::UI<'_>) -> ! {
+    // Signals sent from outside the terminal, e.g. with kill -INT
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
     loop {
-        match ui.update().await {
+        let result = tokio::select! {
+            result = ui.update() => result,
+            _ = &mut interrupted => ui.quit().await,
+        };
+        match result {
             Ok(true) => (),
             Ok(false) => exit_app(None),
-            Err(error) => exit_app(Some(error.to_string())),
+            Err(error) if error.is_fatal() => exit_app(Some(error.to_string())),
+            Err(error) => ui.app_state.push_message(StatusMessage::error(error.to_string())),
         }
     }
 }
 
+/// Set by --quiet, for the errors to leave stdout to the output of --list and --cluster-info
+static QUIET: AtomicBool = AtomicBool::new(false);
+
 fn exit_app(error: Option<String>) -> ! {
     if let Some(err_msg) = error {
-        println!("An error occurred: {}", err_msg);
+        tracing::error!("{}", err_msg);
+        if QUIET.load(Ordering::Relaxed) {
+            eprintln!("{}", err_msg);
+        } else {
+            println!("An error occurred: {}", err_msg);
+        }
         std::process::exit(1)
     } else {
         std::process::exit(0)
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// Sends the logs to `path`, since the UI takes over the terminal.
+fn init_logging(path: &Path) -> std::io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_target(false)
+        .init();
+    Ok(())
+}
 
-    let Args { namespace: namespace_opt } = Args::collect();
+#[tokio::main]
+async fn main() {
 
-    let mut app_state = AppState::new(namespace_opt).await?;
-    let mut ui = UI::new(&mut app_state);
+    let Args { namespace: namespaces, all_namespaces, service_type: service_types, context: context_opt, kubeconfig: kubeconfig_opt, config: _, refresh_interval, startup_retries, max_retries, connect_timeout, shutdown_timeout, bind_address, proxy_mode, dry_run, no_confirm, include_routes, restore, forwards, selector, port, api_port, metrics_port, log_file, tick_rate, list, cluster_info, output, keymap, theme, compact, read_only, quiet } = Args::collect();
+    QUIET.store(quiet, Ordering::Relaxed);
 
-    run_app(&mut ui).await;
+    if let Some(log_file) = &log_file {
+        if let Err(error) = init_logging(log_file) {
+            exit_app(Some(format!("Unable to open the log file {}: {}", log_file.display(), error)));
+        }
+    }
+    let (bind_address, malformed_bind_address) = match cli::parse_bind_address(&bind_address) {
+        Ok(bind_address) => (bind_address, None),
+        Err(text) => (IpAddr::V4(Ipv4Addr::LOCALHOST), Some(text)),
+    };
+    let mut app_state = match AppState::new(namespaces, all_namespaces, context_opt, kubeconfig_opt, ForwardOptions { max_retries, bind_address, proxy_mode, confirm_forward_all: !no_confirm, dry_run, connect_timeout: Duration::from_secs(connect_timeout), shutdown_timeout: Duration::from_secs(shutdown_timeout) }, include_routes, service_types).await {
+        Ok(app_state) => app_state,
+        Err(error) => exit_app(Some(error.to_string())),
+    };
+    if let Some(text) = malformed_bind_address {
+        let text = format!("Invalid --bind-address: {}, listening on {} instead", text, bind_address);
+        // Without the UI, there's no messages pane to show it in
+        if cluster_info || list {
+            eprintln!("{}", text);
+        } else {
+            app_state.push_message(StatusMessage::warning(text));
+        }
+    }
+    if cluster_info {
+        match app_state.server_version().await {
+            Ok(version) => output::print_cluster_info(&version, &app_state.namespaces_label()),
+            Err(error) => exit_app(Some(format!("Unable to get the version of the cluster: {}", error))),
+        }
+        return;
+    }
+    if list {
+        let services = match app_state.fetch_services(startup_retries).await {
+            Ok(services) => services,
+            Err(error) => exit_app(Some(format!("Unable to list services: {}", error))),
+        };
+        if let Err(error) = output::print_services(&services, output) {
+            exit_app(Some(format!("Unable to print the services: {}", error)));
+        }
+        return;
+    }
+    // Served on the loopback address of the family the forwards listen on
+    let loopback = if bind_address.is_ipv6() { IpAddr::V6(Ipv6Addr::LOCALHOST) } else { IpAddr::V4(Ipv4Addr::LOCALHOST) };
+    if let Some(api_port) = api_port {
+        if let Err(error) = api::spawn(loopback, api_port, app_state.subscribe_forwards()) {
+            exit_app(Some(format!("Unable to serve the status API on port {}: {}", api_port, error)));
+        }
+    }
+    if let Some(metrics_port) = metrics_port {
+        if let Err(error) = api::spawn_metrics(loopback, metrics_port, app_state.subscribe_forwards()) {
+            exit_app(Some(format!("Unable to serve the metrics on port {}: {}", metrics_port, error)));
+        }
+    }
+    app_state.queue_launch_forwards(LaunchForwards { restore, forwards, selector: selector.zip(port) });
+    app_state.spawn_service_refresh(Duration::from_secs(refresh_interval), startup_retries);
+    let mut ui = UI::new(&mut app_state, keymap, theme, compact, read_only, Duration::from_millis(tick_rate));
 
-    Ok(())
+    run_app(&mut ui).await
 }
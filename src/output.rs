@@ -0,0 +1,100 @@
+use k8s_openapi::{api::core::v1::Service, apimachinery::pkg::{util::intstr::IntOrString, version::Info}};
+use kube::ResourceExt;
+use serde::Serialize;
+
+use crate::cli::OutputFormat;
+
+/// A service as printed by `--list`
+#[derive(Serialize)]
+struct ServiceSummary {
+    namespace: Option<String>,
+    name: String,
+    #[serde(rename = "type")]
+    service_type: String,
+    cluster_ip: Option<String>,
+    ports: Vec<PortSummary>,
+}
+
+#[derive(Serialize)]
+struct PortSummary {
+    port: i32,
+    name: Option<String>,
+    protocol: String,
+    target_port: Option<IntOrString>,
+}
+
+impl ServiceSummary {
+    fn from_service(service: &Service) -> ServiceSummary {
+        let spec = service.spec.as_ref();
+        ServiceSummary {
+            namespace: service.namespace(),
+            name: service.name(),
+            service_type: spec
+                .and_then(|spec| spec.type_.clone())
+                .unwrap_or_else(|| "ClusterIP".to_string()),
+            cluster_ip: spec.and_then(|spec| spec.cluster_ip.clone()),
+            ports: spec
+                .and_then(|spec| spec.ports.as_ref())
+                .iter()
+                .flat_map(|ports| ports.iter())
+                .map(|port| PortSummary {
+                    port: port.port,
+                    name: port.name.clone(),
+                    protocol: port.protocol.clone().unwrap_or_else(|| "TCP".to_string()),
+                    target_port: port.target_port.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Prints the version of the cluster and the namespace the services would be listed from.
+pub fn print_cluster_info(version: &Info, namespace: &str) {
+    println!("Server version: {}", version.git_version);
+    println!("Platform:       {}", version.platform);
+    println!("Namespace:      {}", namespace);
+}
+
+/// Prints the services and their ports to stdout, for scripting.
+pub fn print_services(services: &[Service], format: OutputFormat) -> Result<(), serde_json::Error> {
+    let summaries: Vec<ServiceSummary> = services
+        .iter()
+        .filter(|service| service.metadata.name.is_some())
+        .map(ServiceSummary::from_service)
+        .collect();
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&summaries)?),
+        OutputFormat::Table => print_table(&summaries),
+    }
+    Ok(())
+}
+
+/// Prints a `kubectl get services`-like table.
+fn print_table(summaries: &[ServiceSummary]) {
+    let mut rows = vec![["NAMESPACE", "NAME", "TYPE", "CLUSTER-IP", "PORTS"].map(str::to_string)];
+    rows.extend(summaries.iter().map(|summary| [
+        summary.namespace.clone().unwrap_or_else(|| "-".to_string()),
+        summary.name.clone(),
+        summary.service_type.clone(),
+        summary.cluster_ip.clone().unwrap_or_else(|| "-".to_string()),
+        summary.ports
+            .iter()
+            .map(|port| match &port.name {
+                Some(name) => format!("{}/{} ({})", port.port, port.protocol, name),
+                None => format!("{}/{}", port.port, port.protocol),
+            })
+            .collect::<Vec<String>>()
+            .join(","),
+    ]));
+    let widths: Vec<usize> = (0..5)
+        .map(|column| rows.iter().map(|row| row[column].chars().count()).max().unwrap_or(0))
+        .collect();
+    for row in rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join("   ").trim_end());
+    }
+}
@@ -0,0 +1,74 @@
+use std::{fs, io, path::PathBuf};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// A forward to re-establish when restoring the previous session
+#[derive(Serialize, Deserialize)]
+pub struct SessionEntry {
+    pub namespace: String,
+    pub service: String,
+    /// Pod the forward targeted directly, if any
+    #[serde(default)]
+    pub pod: Option<String>,
+    /// Container of the pod whose port was forwarded instead of the port of the service, if any
+    #[serde(default)]
+    pub container: Option<String>,
+    #[serde(default)]
+    pub container_port: Option<u16>,
+    pub remote_port: u16,
+    pub local_port: u16,
+}
+
+/// A service port forwarded before, in this session or a previous one
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub namespace: String,
+    pub service: String,
+    pub remote_port: u16,
+    pub local_port: u16,
+}
+
+/// `$XDG_CONFIG_HOME/portnord`, or `~/.config/portnord` when it isn't set.
+pub fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|config_home| config_home.join("portnord"))
+}
+
+fn config_path(file_name: &str) -> io::Result<PathBuf> {
+    config_dir()
+        .map(|dir| dir.join(file_name))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unable to determine the config directory"))
+}
+
+fn read<T: DeserializeOwned>(file_name: &str) -> io::Result<T> {
+    let contents = fs::read_to_string(config_path(file_name)?)?;
+    serde_json::from_str(&contents).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+fn write<T: Serialize + ?Sized>(file_name: &str, value: &T) -> io::Result<()> {
+    let path = config_path(file_name)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let contents = serde_json::to_string_pretty(value).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    fs::write(path, contents)
+}
+
+pub fn load() -> io::Result<Vec<SessionEntry>> {
+    read("session.json")
+}
+
+pub fn save(entries: &[SessionEntry]) -> io::Result<()> {
+    write("session.json", entries)
+}
+
+/// Reads the ports forwarded before, most recent first.
+pub fn load_history() -> io::Result<Vec<HistoryEntry>> {
+    read("history.json")
+}
+
+pub fn save_history(entries: &[HistoryEntry]) -> io::Result<()> {
+    write("history.json", entries)
+}
@@ -1,255 +1,3598 @@
-use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
+use std::{collections::{BTreeMap, BTreeSet, VecDeque}, fmt, net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr}, ops::RangeInclusive, path::PathBuf, sync::{Arc, atomic::{AtomicU64, Ordering}}, time::{Duration, Instant}};
 
-use hyper::{service::{make_service_fn, service_fn}, Server, Request, Body, Response};
-use k8s_openapi::api::core::v1::{Service, Pod};
-use kube::{Api, Client, api::ListParams, ResourceExt, Error};
-use tokio::sync::{Mutex, mpsc::Sender};
+use arboard::Clipboard;
+use futures::{future::BoxFuture, TryStreamExt};
+use hyper::{body::HttpBody, client::conn::SendRequest, service::{make_service_fn, service_fn}, Server, Request, Body, Response};
+use k8s_openapi::{api::core::v1::{Endpoints, Service, ServicePort, Pod}, apimachinery::pkg::{util::intstr::IntOrString, version::Info}};
+use kube::{Client, Config, config::{Kubeconfig, KubeConfigOptions}, ResourceExt};
+use tokio::{net::{TcpListener, TcpStream}, sync::{Mutex, mpsc::{self, Sender, UnboundedSender, UnboundedReceiver}, watch}, task::{JoinHandle, JoinSet}, time::MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
 use tui::widgets::ListState;
 
+use crate::{api::ForwardSummary, cli::{ForwardSpec, ProxyMode}, cluster::{ClusterClient, KubeClient}, error::PortnordError, session::{self, HistoryEntry, SessionEntry}};
+
 pub struct AppState {
-    pub namespace_opt: Option<String>,
-    pub ports_by_service: BTreeMap<String, Vec<i32>>,
+    /// Namespaces the services are listed from, the default one of the context when empty
+    pub namespaces: Vec<String>,
+    pub all_namespaces: bool,
+    client: Arc<dyn ClusterClient>,
+    /// Where the client was loaded from, to reload it when the credentials are rejected
+    kubeconfig_opt: Option<PathBuf>,
+    context_opt: Option<String>,
+    /// Whether the cluster rejected the credentials since the services were last listed
+    pub credentials_expired: bool,
+    forward_options: ForwardOptions,
+    /// Types of the services to list, every type when empty
+    service_types: Vec<String>,
+    /// Type of the services shown, cycled through in the UI among the listed ones
+    pub type_filter: Option<String>,
+    /// Whether the OpenShift routes can be listed to forward the service behind them
+    include_routes: bool,
+    pub ports_by_service: BTreeMap<ServiceKey, Vec<PortInfo>>,
+    pub service_meta: BTreeMap<ServiceKey, ServiceMeta>,
+    pub stale_services: BTreeSet<ServiceKey>,
+    /// Ready addresses backing each service, when the endpoints could be listed
+    pub ready_endpoints: BTreeMap<ServiceKey, usize>,
+    /// Whether the first list of services is still being fetched
+    pub loading: bool,
+    /// Forwards to start once the first list of services has been fetched
+    launch_forwards: Option<LaunchForwards>,
+    default_namespace: String,
     pub service_selection: ListState,
     pub port_selection: ListState,
+    /// Port last selected in each service, as an index in its unfiltered ports
+    last_port_selection: BTreeMap<ServiceKey, usize>,
+    /// Service ports marked to be toggled together
+    pub marked_ports: BTreeSet<(ServiceKey, i32)>,
     pub forwarded_ports: Vec<ForwardedPort>,
+    /// Stopped forwards whose local server may not have exited yet
+    stopping_forwards: Vec<ForwardedPort>,
+    pub prompt: Option<Prompt>,
+    pub service_filter: Option<String>,
+    /// Filter of the ports of the selected service, cleared when leaving them
+    pub port_filter: Option<String>,
+    /// Filter being typed, if any
+    pub filtering: Option<FilterTarget>,
+    /// Pane moved in by the navigation keys, the services one whenever no port is selected; see `focused_pane`
+    focus: Pane,
+    pub sort_mode: SortMode,
+    pub pending_quit: bool,
+    /// Whether something changed since the UI was last drawn
+    pub dirty: bool,
+    pub overlay: Option<Overlay>,
+    pub messages: Vec<StatusMessage>,
+    /// Forwards to open in the browser once active
+    open_when_active: BTreeSet<u64>,
+    /// The latest errors, kept in full past the expiry of their message, oldest first
+    pub errors: VecDeque<StatusMessage>,
+    /// Service ports forwarded before, most recent first, saved to disk as they change
+    pub history: VecDeque<HistoryEntry>,
+    clipboard: Option<Clipboard>,
+    message_sender: UnboundedSender<StatusMessage>,
+    message_receiver: UnboundedReceiver<StatusMessage>,
+    refresh_sender: UnboundedSender<ServiceListing>,
+    refresh_receiver: UnboundedReceiver<ServiceListing>,
+    forward_update_sender: UnboundedSender<ForwardUpdate>,
+    forward_update_receiver: UnboundedReceiver<ForwardUpdate>,
+    /// Forwards started in the background when forwarding all the ports of a service, `None` for each that failed
+    bulk_forward_sender: UnboundedSender<Option<ForwardedPort>>,
+    bulk_forward_receiver: UnboundedReceiver<Option<ForwardedPort>>,
+    /// Ports of a service being forwarded at once in the background
+    pub bulk_forward: Option<BulkForward>,
+    /// Forwards restarted, sent back once the server of their previous run exited or the shutdown timeout elapsed
+    restart_sender: UnboundedSender<Restart>,
+    restart_receiver: UnboundedReceiver<Restart>,
+    auth_failure_sender: UnboundedSender<()>,
+    auth_failure_receiver: UnboundedReceiver<()>,
+    /// Latest forwards, for the status API
+    forward_summaries: watch::Sender<Vec<ForwardSummary>>,
+    /// Background refresh of the services, restarted when switching namespaces
+    refresh_task: Option<(Duration, JoinHandle<()>)>,
+    next_forward_id: u64,
+}
+
+/// A service, identified by its namespace as well since services of different namespaces may share a name
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ServiceKey {
+    pub namespace: String,
+    pub name: String,
+}
+
+/// Just the name, see `AppState::service_label` for the name qualified by the namespace
+impl fmt::Display for ServiceKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
 }
 
 pub struct ForwardedPort {
+    pub id: u64,
+    pub namespace: String,
     pub service: String,
+    /// Pod targeted directly, rather than one picked among those backing the service
+    pub pod: Option<String>,
+    /// Pod the forward last connected to
+    pub pod_name: Option<String>,
+    /// Node that pod runs on, once scheduled
+    pub node_name: Option<String>,
+    /// Port of a container of `pod` forwarded instead of the port of the service
+    pub container_port: Option<ContainerPort>,
     pub port: u16,
+    pub bind_address: IpAddr,
+    pub local_port: u16,
+    pub status: ForwardStatus,
+    /// When the forward was started, kept when it reconnects
+    pub started_at: Instant,
+    /// When the forward last started reconnecting, if ever
+    pub last_reconnect: Option<Instant>,
+    /// How many times the forward started reconnecting
+    pub reconnects: u64,
+    pub traffic: Arc<Traffic>,
     pub sender: Sender<()>
 }
 
-impl AppState {
-    pub async fn new(namespace_opt: Option<String>) -> Result<AppState, Error> {
-        let services = AppState::get_services(&namespace_opt).await?;
-        let ports_by_service: BTreeMap<String, Vec<i32>> = services
-        .iter()
-        .map(|svc| 
-            (
-                svc.metadata.name.to_owned().unwrap(), 
-                svc.clone().spec.unwrap().ports.unwrap().iter().map(|port| port.port).collect::<Vec<i32>>()
-            )
-        )
-        .collect();
+/// Bytes proxied by a forward, tallied by its server as the bodies stream through
+#[derive(Default)]
+pub struct Traffic {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
 
-        Ok(AppState { 
-            namespace_opt,
-            ports_by_service,
-            service_selection: ListState::default(), 
-            port_selection: ListState::default(),
-            forwarded_ports: vec![]
-        })
+impl Traffic {
+    /// Bytes sent to the pod
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
     }
 
-    pub fn forwarded_ports_for_service(&self, service: &str) -> Vec<&ForwardedPort> {
-        self.forwarded_ports
-            .iter()
-            .filter(|fw_port| fw_port.service == service).collect()
+    /// Bytes received from the pod
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
     }
+}
 
-    pub fn forwarded_ports_for_selected_service(&self) -> Vec<&ForwardedPort> {
-        if let Some(service) = self.service() {
-            self.forwarded_ports_for_service(&service)
-        } else {
-            vec![]
+/// Settings shared by every forward
+#[derive(Clone)]
+pub struct ForwardOptions {
+    /// Attempts to re-establish a forward whose connection ended
+    pub max_retries: u32,
+    /// Local address the forwards listen on
+    pub bind_address: IpAddr,
+    pub proxy_mode: ProxyMode,
+    /// Whether forwarding all the ports of a service at once asks for confirmation first
+    pub confirm_forward_all: bool,
+    /// Record forwards without connecting to the pods nor binding local ports
+    pub dry_run: bool,
+    /// How long connecting a forward to its pod may take before it is abandoned
+    pub connect_timeout: Duration,
+    /// How long quitting waits for the servers of the forwards to exit
+    pub shutdown_timeout: Duration,
+}
+
+/// Services fetched by the background refresh, with their endpoints unless those couldn't be listed
+struct ServiceListing {
+    services: Vec<Service>,
+    endpoints: Option<Vec<Endpoints>>,
+}
+
+/// Forwards requested on the command line, started once the services are known
+pub struct LaunchForwards {
+    pub restore: bool,
+    pub forwards: Vec<ForwardSpec>,
+    /// Label selector of the services to forward a port of, and that port
+    pub selector: Option<(String, u16)>,
+}
+
+/// Delay before the first reconnection attempt, doubled on each following one
+const RECONNECT_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(10);
+/// How long the local port of a new forward has to accept a connection
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Pod a forward connects to
+struct PodTarget {
+    namespace: String,
+    name: String,
+    node: Option<String>,
+    /// Port of the pod to forward, `RemotePort` resolved against its containers
+    port: u16,
+    ready: bool,
+}
+
+/// How a forward finds the pod to connect to
+#[derive(Clone)]
+enum PodSelector {
+    /// A pod targeted directly
+    Name(String),
+    /// The pods matching the selector of the service, `None` for services without one, which no pod backs
+    Labels(Option<String>),
+}
+
+/// Port of the pods a forward connects to, the target port of a service port naming a container port rather than numbering one
+#[derive(Clone)]
+enum RemotePort {
+    Number(u16),
+    Named(String),
+}
+
+impl RemotePort {
+    /// Target port of `port`, which defaults to the port of the service itself.
+    fn of(port: &PortInfo) -> RemotePort {
+        match &port.target_port {
+            Some(IntOrString::Int(number)) => RemotePort::Number(*number as u16),
+            Some(IntOrString::String(name)) => RemotePort::Named(name.clone()),
+            None => RemotePort::Number(port.port as u16),
         }
     }
 
-    pub fn service(&self) -> Option<String> {
-        if let Some(selected_service) = self.service_selection.selected() {
-            Some(self.service_list()[selected_service].clone())
-        } else {
-            None
+    /// Number of the port on `pod`, `None` when none of its containers declares a port of that name.
+    fn on(&self, pod: &Pod) -> Option<u16> {
+        match self {
+            RemotePort::Number(number) => Some(*number),
+            RemotePort::Named(name) => pod.spec
+                .iter()
+                .flat_map(|spec| &spec.containers)
+                .flat_map(|container| container.ports.iter().flatten())
+                .find(|port| port.name.as_ref() == Some(name))
+                .map(|port| port.container_port as u16),
         }
     }
+}
 
-    pub fn service_list(&self) -> Vec<String> {
-        self.ports_by_service
-            .keys()
-            .into_iter().map(|service| service.to_owned())
-            .collect()
+impl fmt::Display for RemotePort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RemotePort::Number(number) => write!(f, "{}", number),
+            RemotePort::Named(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Resolves with the reason once the connection of a forward can no longer be used
+type ConnectionEnded = BoxFuture<'static, String>;
+
+impl ForwardedPort {
+    pub fn is_for(&self, service: &ServiceKey) -> bool {
+        self.namespace == service.namespace && self.service == service.name
     }
 
-    pub fn port_list(&self) -> Vec<i32> {
-        if let Some(selected_service) = self.service_selection.selected() {
-            self.ports_by_service
-                .values()
-                .map(|port| port.to_owned())
-                .collect::<Vec<Vec<i32>>>()[selected_service]
-                .to_owned()
-        } else {
-            vec![]
+    pub fn service_key(&self) -> ServiceKey {
+        ServiceKey { namespace: self.namespace.clone(), name: self.service.clone() }
+    }
+
+    pub fn local_address(&self) -> String {
+        SocketAddr::new(self.bind_address, self.local_port).to_string()
+    }
+
+    /// Local address as a URL when `port` looks like HTTP (e.g. `http://127.0.0.1:8080`), as `host:port` otherwise.
+    pub fn local_url(&self, port: &PortInfo) -> String {
+        match port.url_scheme() {
+            Some(scheme) => format!("{}://{}", scheme, self.local_address()),
+            None => self.local_address(),
         }
     }
+}
 
-    pub fn select(&mut self) {
-        if self.port_selection.selected().is_none() {
-            if !self.port_list().is_empty() {
-                self.port_selection.select(Some(0));
-            }
+/// Safety net for forwards dropped without being stopped, which would leave their server running. Stopping a forward
+/// should still go through `sender.send(()).await`: dropping can't wait, and only signals the server best-effort.
+impl Drop for ForwardedPort {
+    fn drop(&mut self) {
+        // Fails when the server already exited or was already told to stop, both fine here
+        let _ = self.sender.try_send(());
+    }
+}
+
+#[derive(Clone)]
+pub enum ForwardStatus {
+    Connecting,
+    Active,
+    Failed(String),
+}
+
+pub enum ForwardChange {
+    Status(ForwardStatus),
+    /// Pod the forward connected to and its node, which may change when reconnecting
+    Pod { name: String, node: Option<String> },
+    /// The local server exited, whether the forward was stopped or not
+    ServerExited,
+}
+
+pub struct ForwardUpdate {
+    pub id: u64,
+    pub change: ForwardChange,
+}
+
+/// Lets the tasks spawned for a forward report its status back to `AppState`.
+#[derive(Clone)]
+struct ForwardReporter {
+    id: u64,
+    updates: UnboundedSender<ForwardUpdate>,
+    messages: UnboundedSender<StatusMessage>,
+    auth_failures: UnboundedSender<()>,
+    traffic: Arc<Traffic>,
+}
+
+impl ForwardReporter {
+    fn report(&self, status: ForwardStatus) {
+        let _ = self.updates.send(ForwardUpdate { id: self.id, change: ForwardChange::Status(status) });
+    }
+
+    fn report_pod(&self, pod_name: &str, node_name: Option<&str>) {
+        let change = ForwardChange::Pod { name: pod_name.to_owned(), node: node_name.map(str::to_owned) };
+        let _ = self.updates.send(ForwardUpdate { id: self.id, change });
+    }
+
+    fn warn(&self, text: String) {
+        let _ = self.messages.send(StatusMessage::warning(text));
+    }
+
+    /// Lets `AppState` know when `error` means the credentials were rejected.
+    fn check_auth(&self, error: &kube::Error) {
+        if is_unauthorized(error) {
+            let _ = self.auth_failures.send(());
         }
     }
+}
 
-    pub fn deselect(&mut self) {
-        if let Some(_) = self.port_selection.selected() {
-            self.port_selection.select(None);
+/// Held by the local server task of a forward, reporting its exit even when it panics.
+struct ServerExit {
+    reporter: ForwardReporter,
+    /// Cancelled on exit, to close the connections still open
+    stopped: CancellationToken,
+}
+
+impl Drop for ServerExit {
+    fn drop(&mut self) {
+        self.stopped.cancel();
+        let _ = self.reporter.updates.send(ForwardUpdate { id: self.reporter.id, change: ForwardChange::ServerExited });
+    }
+}
+
+#[derive(Clone)]
+pub struct PortInfo {
+    pub port: i32,
+    pub name: Option<String>,
+    pub target_port: Option<IntOrString>,
+    pub protocol: String,
+}
+
+impl PortInfo {
+    fn from_service_port(service_port: &ServicePort) -> PortInfo {
+        PortInfo {
+            port: service_port.port,
+            name: service_port.name.clone(),
+            target_port: service_port.target_port.clone(),
+            protocol: service_port.protocol.clone().unwrap_or_else(|| "TCP".to_string()),
         }
     }
 
-    pub fn next(&mut self) {
-        if let Some(selected_port) = self.port_selection.selected() {
-            self.port_selection.select(Some((selected_port + 1) % self.port_list().len()));
-        } else if let Some(selected_service) = self.service_selection.selected() {
-            self.service_selection.select(Some((selected_service + 1) % self.service_list().len()));
-        } else if !self.service_list().is_empty() {
-            self.service_selection.select(Some(0));
+    /// Only TCP ports can be forwarded, the Kubernetes port forwarding API doesn't relay UDP or SCTP.
+    pub fn is_tcp(&self) -> bool {
+        self.protocol == "TCP"
+    }
+
+    /// Port number, followed by its name when it has one (e.g. `8080 (http)`).
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{} ({})", self.port, name),
+            None => self.port.to_string(),
         }
     }
 
-    pub fn previous(&mut self) {
-        if let Some(selected_port) = self.port_selection.selected() {
-            let port_list_len = self.port_list().len() as i32;
-            self.port_selection.select(Some((((selected_port as i32 - 1) + port_list_len) % port_list_len) as usize));
-        } else if let Some(selected_service) = self.service_selection.selected() {
-            let svc_list_len = self.service_list().len() as i32;
-            self.service_selection.select(Some((((selected_service as i32 - 1) + svc_list_len) % svc_list_len) as usize));
-            self.port_selection.select(None);
-        } else if !self.service_list().is_empty() {
-            self.service_selection.select(Some(self.service_list().len() - 1));
+    /// Scheme of the port when its name or number looks like HTTP, e.g. `https` for port 443.
+    pub fn url_scheme(&self) -> Option<&'static str> {
+        match (self.name.as_deref(), self.port) {
+            (Some("https"), _) | (_, 443) => Some("https"),
+            (Some("http"), _) | (_, 80 | 8080) => Some("http"),
+            _ => None,
         }
     }
+}
 
-    pub async fn toggle_port_forwarding(&mut self) -> Result<(), kube::Error> {
-        if let Some(selected_port) = self.port_selection.selected() {
-            let selected_svc = &self.service_list()[self.service_selection.selected().unwrap()];
-            let selected_port = self.port_list()[selected_port] as u16;
-            let forwarded_ports = &mut self.forwarded_ports;
-            if let Some(existing_forwarded_port_idx) = forwarded_ports.into_iter().position(|port| {
-                &port.service == selected_svc && port.port == selected_port
-            }) {
-                let existing_forwarded_port = &forwarded_ports[existing_forwarded_port_idx];
-                if let Ok(()) = existing_forwarded_port.sender.send(()).await {
-                    forwarded_ports.remove(existing_forwarded_port_idx);
-                }
-                Ok(())
-            } else {
-                if let Some(sender) = AppState::run_port_forward(&self.namespace_opt, &selected_svc, selected_port).await? {
-                    self.forwarded_ports.push(ForwardedPort { service: selected_svc.clone(), port: selected_port, sender });
+pub struct ServiceMeta {
+    pub service_type: String,
+    pub cluster_ip: Option<String>,
+    pub selector: Option<BTreeMap<String, String>>,
+    pub labels: BTreeMap<String, String>,
+    pub annotations: BTreeMap<String, String>,
+}
+
+impl ServiceMeta {
+    fn from_service(service: &Service) -> ServiceMeta {
+        let spec = service.spec.as_ref();
+        ServiceMeta {
+            service_type: service_type_of(service).to_owned(),
+            cluster_ip: spec.and_then(|spec| spec.cluster_ip.clone()),
+            selector: spec.and_then(|spec| spec.selector.clone()),
+            labels: service.labels().clone(),
+            annotations: service.annotations().clone(),
+        }
+    }
+
+    /// The selector as `key=value` pairs joined by commas, `None` when the service doesn't select pods.
+    pub fn selector_label(&self) -> Option<String> {
+        match &self.selector {
+            Some(selector) if !selector.is_empty() => Some(selector
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<String>>()
+                .join(",")),
+            _ => None,
+        }
+    }
+
+    /// Headless services have no cluster IP to proxy through, a forward always landing on one of their pods.
+    pub fn is_headless(&self) -> bool {
+        self.cluster_ip.as_deref() == Some("None")
+    }
+}
+
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+}
+
+pub enum PromptKind {
+    /// Local port to bind when forwarding the selected remote port
+    LocalPort { service: ServiceKey, port: u16 },
+    /// Range of remote ports of a service to forward at once, as `START-END`
+    PortRange { service: ServiceKey },
+}
+
+impl PromptKind {
+    /// Whether `c` may be typed in the prompt.
+    pub fn accepts(&self, c: char) -> bool {
+        match self {
+            PromptKind::LocalPort { .. } => c.is_ascii_digit(),
+            PromptKind::PortRange { .. } => c.is_ascii_digit() || c == '-',
+        }
+    }
+}
+
+/// List of the main screen the navigation keys move in
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Services,
+    Ports,
+}
+
+/// List a filter applies to
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FilterTarget {
+    Services,
+    Ports,
+}
+
+/// Order in which the services are listed
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    NameAscending,
+    NameDescending,
+    /// Services with the most active forwards first
+    ActiveForwards,
+}
+
+impl SortMode {
+    fn next(self) -> SortMode {
+        match self {
+            SortMode::NameAscending => SortMode::NameDescending,
+            SortMode::NameDescending => SortMode::ActiveForwards,
+            SortMode::ActiveForwards => SortMode::NameAscending,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::NameAscending => "name ↑",
+            SortMode::NameDescending => "name ↓",
+            SortMode::ActiveForwards => "forwards",
+        }
+    }
+}
+
+/// Modal drawn on top of the panes, capturing the keys while open
+pub enum Overlay {
+    Help,
+    Pods(PodPicker),
+    Namespaces(NamespacePicker),
+    ForwardAll(ForwardAllConfirmation),
+    ServiceInfo(ServiceInfo),
+    Routes(RoutePicker),
+    Errors(ErrorLog),
+    History(HistoryPicker),
+}
+
+/// The history kept by `AppState`, to forward one of the ports forwarded before again
+pub struct HistoryPicker {
+    pub selection: ListState,
+}
+
+/// The errors kept by `AppState`, scrolled line by line
+pub struct ErrorLog {
+    pub scroll: u16,
+}
+
+/// Labels and annotations of a service, scrolled line by line
+pub struct ServiceInfo {
+    pub service: ServiceKey,
+    pub scroll: u16,
+}
+
+/// Ports of a service about to be forwarded at once, waiting for a second Enter
+pub struct ForwardAllConfirmation {
+    pub service: ServiceKey,
+    pub ports: Vec<u16>,
+    pub bind_address: IpAddr,
+}
+
+impl ForwardAllConfirmation {
+    pub fn local_addresses(&self) -> Vec<String> {
+        self.ports.iter().map(|port| SocketAddr::new(self.bind_address, *port).to_string()).collect()
+    }
+}
+
+/// Type of `service`, ClusterIP when not set.
+fn service_type_of(service: &Service) -> &str {
+    service.spec.as_ref().and_then(|spec| spec.type_.as_deref()).unwrap_or("ClusterIP")
+}
+
+/// How many of the ports of a service forwarded at once are set up, whether they succeeded or not
+pub struct BulkForward {
+    pub service: ServiceKey,
+    pub done: usize,
+    pub total: usize,
+    /// Updates reported by the forwards before they were set up, by forward id, applied once they are
+    early_updates: BTreeMap<u64, Vec<ForwardChange>>,
+}
+
+/// A forward to start again with the same ports, once the server of its previous run exited
+struct Restart {
+    service: ServiceKey,
+    pod: Option<String>,
+    container_port: Option<ContainerPort>,
+    port: u16,
+    local_port: u16,
+    /// Whether the server exited within the shutdown timeout
+    exited: bool,
+}
+
+/// Namespaces to switch the services list to, `None` standing for all of them
+pub struct NamespacePicker {
+    pub namespaces: Vec<Option<String>>,
+    pub selection: ListState,
+}
+
+/// OpenShift routes of the listed namespaces, to forward the service port they expose
+pub struct RoutePicker {
+    pub routes: Vec<RouteEntry>,
+    pub selection: ListState,
+}
+
+/// An OpenShift route with the service and port it sends traffic to, `None` when the port isn't one of the listed service
+pub struct RouteEntry {
+    pub name: String,
+    pub host: Option<String>,
+    pub service: ServiceKey,
+    pub port: Option<u16>,
+}
+
+/// Pods backing a service, to forward one of its ports, or one of their container ports, to a specific replica
+pub struct PodPicker {
+    pub service: ServiceKey,
+    pub port: u16,
+    pub pods: Vec<PodChoice>,
+    pub selection: ListState,
+}
+
+/// A pod to forward the port of the service to, or one of its container ports
+pub struct PodChoice {
+    pub pod: String,
+    pub container_port: Option<ContainerPort>,
+}
+
+/// TCP port declared by a container of a pod
+#[derive(Clone)]
+pub struct ContainerPort {
+    pub container: String,
+    pub port: u16,
+    pub name: Option<String>,
+}
+
+impl ContainerPort {
+    /// Container and port number, followed by the port name when it has one (e.g. `app:8080 (http)`).
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{}:{} ({})", self.container, self.port, name),
+            None => format!("{}:{}", self.container, self.port),
+        }
+    }
+}
+
+/// How long a status message stays visible
+const MESSAGE_TTL: Duration = Duration::from_secs(10);
+/// How many errors the error log keeps
+const MAX_ERRORS: usize = 50;
+/// How many ports the history of forwards keeps
+const MAX_HISTORY: usize = 30;
+
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+pub struct StatusMessage {
+    pub severity: Severity,
+    pub text: String,
+    pub created_at: Instant,
+}
+
+impl StatusMessage {
+    pub fn new(severity: Severity, text: impl Into<String>) -> StatusMessage {
+        StatusMessage { severity, text: text.into(), created_at: Instant::now() }
+    }
+
+    pub fn info(text: impl Into<String>) -> StatusMessage {
+        StatusMessage::new(Severity::Info, text)
+    }
+
+    pub fn warning(text: impl Into<String>) -> StatusMessage {
+        StatusMessage::new(Severity::Warning, text)
+    }
+
+    pub fn error(text: impl Into<String>) -> StatusMessage {
+        StatusMessage::new(Severity::Error, text)
+    }
+
+    fn is_expired(&self) -> bool {
+        self.created_at.elapsed() > MESSAGE_TTL
+    }
+}
+
+impl AppState {
+    pub async fn new(
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+        context_opt: Option<String>,
+        kubeconfig_opt: Option<PathBuf>,
+        forward_options: ForwardOptions,
+        include_routes: bool,
+        service_types: Vec<String>
+    ) -> Result<AppState, Box<dyn std::error::Error>> {
+        let config = AppState::load_config(kubeconfig_opt.clone(), context_opt.clone()).await?;
+        let default_namespace = config.default_namespace.clone();
+        let client = Arc::new(KubeClient::new(Client::try_from(config)?, default_namespace));
+        AppState::with_client(client, namespaces, all_namespaces, context_opt, kubeconfig_opt, forward_options, include_routes, service_types).await
+    }
+
+    /// Like `new`, with the cluster already reached through `client`.
+    #[allow(clippy::too_many_arguments)]
+    async fn with_client(
+        client: Arc<dyn ClusterClient>,
+        namespaces: Vec<String>,
+        all_namespaces: bool,
+        context_opt: Option<String>,
+        kubeconfig_opt: Option<PathBuf>,
+        forward_options: ForwardOptions,
+        include_routes: bool,
+        service_types: Vec<String>
+    ) -> Result<AppState, Box<dyn std::error::Error>> {
+        let default_namespace = client.default_namespace().to_owned();
+        for namespace in &namespaces {
+            AppState::check_namespace(client.as_ref(), namespace).await?;
+        }
+        let (message_sender, message_receiver) = mpsc::unbounded_channel();
+        let (refresh_sender, refresh_receiver) = mpsc::unbounded_channel();
+        let (forward_update_sender, forward_update_receiver) = mpsc::unbounded_channel();
+        let (bulk_forward_sender, bulk_forward_receiver) = mpsc::unbounded_channel();
+        let (restart_sender, restart_receiver) = mpsc::unbounded_channel();
+        let (auth_failure_sender, auth_failure_receiver) = mpsc::unbounded_channel();
+        let (forward_summaries, _) = watch::channel(vec![]);
+        let history = match session::load_history() {
+            Ok(history) => history.into_iter().take(MAX_HISTORY).collect(),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => VecDeque::new(),
+            Err(error) => {
+                tracing::warn!("Unable to read the history of forwards: {}", error);
+                VecDeque::new()
+            }
+        };
+
+        Ok(AppState {
+            namespaces,
+            all_namespaces,
+            client,
+            kubeconfig_opt,
+            context_opt,
+            credentials_expired: false,
+            forward_options,
+            include_routes,
+            service_types,
+            type_filter: None,
+            ports_by_service: BTreeMap::new(),
+            service_meta: BTreeMap::new(),
+            stale_services: BTreeSet::new(),
+            ready_endpoints: BTreeMap::new(),
+            loading: true,
+            launch_forwards: None,
+            default_namespace,
+            service_selection: ListState::default(),
+            port_selection: ListState::default(),
+            last_port_selection: BTreeMap::new(),
+            marked_ports: BTreeSet::new(),
+            forwarded_ports: vec![],
+            stopping_forwards: vec![],
+            prompt: None,
+            service_filter: None,
+            port_filter: None,
+            filtering: None,
+            focus: Pane::Services,
+            sort_mode: SortMode::NameAscending,
+            pending_quit: false,
+            dirty: true,
+            overlay: None,
+            messages: vec![],
+            open_when_active: BTreeSet::new(),
+            errors: VecDeque::new(),
+            history,
+            clipboard: None,
+            message_sender,
+            message_receiver,
+            refresh_sender,
+            refresh_receiver,
+            forward_update_sender,
+            forward_update_receiver,
+            bulk_forward_sender,
+            bulk_forward_receiver,
+            bulk_forward: None,
+            restart_sender,
+            restart_receiver,
+            auth_failure_sender,
+            auth_failure_receiver,
+            forward_summaries,
+            refresh_task: None,
+            next_forward_id: 0,
+        })
+    }
+
+    /// Fetches the services once, without the background refresh, retrying up to `retries` times.
+    pub async fn fetch_services(&self, retries: u32) -> Result<Vec<Service>, kube::Error> {
+        let ServiceListing { services, .. } = self.get_services_with_retries(retries).await?;
+        Ok(services.into_iter().filter(|svc| self.has_listed_type(svc)).collect())
+    }
+
+    /// Retries failed listings with an exponential backoff starting at a second, except when the credentials are rejected.
+    async fn get_services_with_retries(&self, retries: u32) -> Result<ServiceListing, kube::Error> {
+        let mut backoff = Duration::from_secs(1);
+        let mut attempt = 0;
+        loop {
+            match AppState::get_services(self.client.as_ref(), &self.namespaces, self.all_namespaces, None).await {
+                Err(error) if attempt < retries && !is_unauthorized(&error) => {
+                    attempt += 1;
+                    tracing::warn!("Unable to list services: {}", error);
+                    // Kept off stdout, which --list may write JSON to
+                    eprintln!("Unable to list services ({}), retrying in {}s… ({}/{})", error, backoff.as_secs(), attempt, retries);
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
                 }
-                Ok(())
+                result => return result,
             }
-        } else {
-            let selected_svc = &self.service_list()[self.service_selection.selected().unwrap()];
-            let all_svc_ports = &self.ports_by_service[selected_svc];
-            let svc_forwarded_ports = self.forwarded_ports_for_selected_service();
-            let should_stop_port_forwarding = all_svc_ports.len() == svc_forwarded_ports.len();
+        }
+    }
 
-            if should_stop_port_forwarding {
-                let forwarded_ports_futs: Vec<_> = svc_forwarded_ports.iter().map(|port| async { 
-                    port.sender.send(()).await 
-                }).map(Box::pin).collect();
-                if let (Err(error), _, _) = futures::future::select_all(forwarded_ports_futs).await {
-                    println!("An error occurred stopping port forwarding for service '{}': {}", selected_svc, error);
-                    return Ok(());
-                }
-                self.forwarded_ports.retain(|port| &port.service != selected_svc);
-            } else {
-                let namespace_opt = &self.namespace_opt;
-                let forwarded_ports = Arc::new(Mutex::new(&mut self.forwarded_ports));
-                let forwarded_ports_futs: Vec<_> = all_svc_ports.iter().map(|port| async {
-                    match AppState::run_port_forward(namespace_opt, &selected_svc, port.to_owned() as u16).await {
-                        Ok(Some(sender)) => {
-                            forwarded_ports.lock().await.push(ForwardedPort { service: selected_svc.clone(), port: (port.to_owned() as u16), sender })
+    /// Defers the launch forwards until the first list of services has been fetched.
+    pub fn queue_launch_forwards(&mut self, launch_forwards: LaunchForwards) {
+        self.launch_forwards = Some(launch_forwards);
+    }
+
+    /// Fetches the services right away, retrying up to `startup_retries` times, then every `interval`, in the background; see `update_services`.
+    pub fn spawn_service_refresh(&mut self, interval: Duration, startup_retries: u32) {
+        let client = self.client.clone();
+        let namespaces = self.namespaces.clone();
+        let all_namespaces = self.all_namespaces;
+        let refresh_sender = self.refresh_sender.clone();
+        let message_sender = self.message_sender.clone();
+        let auth_failure_sender = self.auth_failure_sender.clone();
+        let (kubeconfig_opt, context_opt) = (self.kubeconfig_opt.clone(), self.context_opt.clone());
+        let refresh_task = tokio::spawn(async move {
+            let mut client = client;
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut loaded = false;
+            let (mut attempt, mut backoff) = (0, Duration::from_secs(1));
+            loop {
+                ticker.tick().await;
+                let mut result = AppState::get_services(client.as_ref(), &namespaces, all_namespaces, None).await;
+                // The first listing is retried with a backoff on flaky networks, the loading spinner staying up meanwhile
+                while let Err(error) = &result {
+                    if loaded || attempt == startup_retries || is_unauthorized(error) {
+                        break;
+                    }
+                    attempt += 1;
+                    tracing::warn!("Unable to list services: {}", error);
+                    let text = format!("Unable to load services ({}), retrying in {}s… ({}/{})", error, backoff.as_secs(), attempt, startup_retries);
+                    let _ = message_sender.send(StatusMessage::warning(text));
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    result = AppState::get_services(client.as_ref(), &namespaces, all_namespaces, None).await;
+                }
+                match result {
+                    Ok(listing) => {
+                        loaded = true;
+                        if refresh_sender.send(listing).is_err() {
+                            break;
                         }
-                        Err(error) => {
-                            println!("An error occurred forwarding port {} for service {}: {}", port.to_owned(), selected_svc.clone(), error.to_string());
+                    }
+                    Err(error) if is_unauthorized(&error) => {
+                        let _ = auth_failure_sender.send(());
+                        // Renewed credentials are picked up on the next tick
+                        if let Some(reloaded_client) = AppState::reload_client(kubeconfig_opt.clone(), context_opt.clone()).await {
+                            client = reloaded_client;
                         }
-                        _ => ()
                     }
-                }).map(Box::pin).collect();
+                    Err(error) => {
+                        let action = if loaded { "refresh" } else { "load" };
+                        let _ = message_sender.send(StatusMessage::warning(format!("Unable to {} services: {}", action, error)));
+                    }
+                }
+            }
+        });
+        self.refresh_task = Some((interval, refresh_task));
+    }
+
+    /// Fetches the services once in the background, on top of the periodic refresh; see `update_services`.
+    pub fn refresh_services(&mut self) {
+        let client = self.client.clone();
+        let namespaces = self.namespaces.clone();
+        let all_namespaces = self.all_namespaces;
+        let refresh_sender = self.refresh_sender.clone();
+        let message_sender = self.message_sender.clone();
+        let auth_failure_sender = self.auth_failure_sender.clone();
+        self.push_message(StatusMessage::info("Refreshing services…"));
+        tokio::spawn(async move {
+            match AppState::get_services(client.as_ref(), &namespaces, all_namespaces, None).await {
+                Ok(listing) => {
+                    let _ = refresh_sender.send(listing);
+                }
+                Err(error) => {
+                    if is_unauthorized(&error) {
+                        let _ = auth_failure_sender.send(());
+                    }
+                    let _ = message_sender.send(StatusMessage::warning(format!("Unable to refresh services: {}", error)));
+                }
+            }
+        });
+    }
 
-                futures::future::select_all(forwarded_ports_futs).await;
+    /// Lists the namespaces to pick the one to switch to.
+    pub async fn open_namespace_picker(&mut self) {
+        match self.client.list_namespaces().await {
+            Ok(namespaces) => {
+                let namespaces: Vec<Option<String>> = std::iter::once(None)
+                    .chain(namespaces.iter().map(|namespace| Some(namespace.name())))
+                    .collect();
+                let current = if self.all_namespaces { None } else { Some(self.namespace().to_owned()) };
+                let mut selection = ListState::default();
+                selection.select(Some(namespaces.iter().position(|namespace| namespace == &current).unwrap_or(0)));
+                self.overlay = Some(Overlay::Namespaces(NamespacePicker { namespaces, selection }));
             }
-            Ok(())
+            Err(error) => self.push_message(StatusMessage::error(format!("Unable to list namespaces: {}", error))),
         }
     }
 
-    async fn get_services(namespace_opt: &Option<String>) -> Result<Vec<Service>, kube::Error> {
-        let client = Client::try_default().await?;
-        let service_api: Api<Service> = if let Some(ns) = namespace_opt {
-            Api::namespaced(client, &ns)
-        } else {
-            Api::default_namespaced(client)
-        };
-        let services: Vec<Service> = service_api.list(&ListParams::default()).await?.items;
-        Ok(services)
+    /// Opens the labels and annotations of the selected service.
+    pub fn open_service_info(&mut self) {
+        if let Some(service) = self.service() {
+            self.overlay = Some(Overlay::ServiceInfo(ServiceInfo { service, scroll: 0 }));
+        }
+    }
+
+    /// Scrolls the service info down, the UI keeping the last line visible.
+    pub fn scroll_service_info_down(&mut self) {
+        if let Some(Overlay::ServiceInfo(info)) = &mut self.overlay {
+            info.scroll = info.scroll.saturating_add(1);
+        }
+    }
+
+    pub fn scroll_service_info_up(&mut self) {
+        if let Some(Overlay::ServiceInfo(info)) = &mut self.overlay {
+            info.scroll = info.scroll.saturating_sub(1);
+        }
     }
 
-    async fn run_port_forward(namespace_opt: &Option<String>, service: &str, port: u16) -> Result<Option<Sender<()>>, kube::Error> {
-        let client = Client::try_default().await?;
-        let pod_api: Api<Pod> = if let Some(ns) = namespace_opt {
-            Api::namespaced(client, &ns)
+    /// Opens the latest errors, in full.
+    pub fn open_error_log(&mut self) {
+        if self.errors.is_empty() {
+            self.push_message(StatusMessage::info("No error so far"));
         } else {
-            Api::default_namespaced(client)
-        };
-        let pod_opt = pod_api
-            .list(&ListParams::default())
-            .await
-            .iter()
-            .flat_map(|pods| pods.items.to_owned())
-            // FIXME: This looks for a pod whose name has the service 
-            //        name as prefix and might select an unrelated pod.
-            .find(|pod| pod.name().starts_with(service));
-    
-        if let Some(pod) = pod_opt {
-            let mut port_forwarder = pod_api.portforward(&pod.name(), &vec![port]).await?;
-            let stream = port_forwarder.take_stream(port).unwrap();
-            let (sender, connection) = hyper::client::conn::handshake(stream).await.unwrap();
-            tokio::spawn(async move {
-                if let Err(e) = connection.await {
-                    println!("Connection on port {} failed: {}", port, e);
+            self.overlay = Some(Overlay::Errors(ErrorLog { scroll: 0 }));
+        }
+    }
+
+    pub fn scroll_error_log_down(&mut self) {
+        if let Some(Overlay::Errors(log)) = &mut self.overlay {
+            log.scroll = log.scroll.saturating_add(1);
+        }
+    }
+
+    pub fn scroll_error_log_up(&mut self) {
+        if let Some(Overlay::Errors(log)) = &mut self.overlay {
+            log.scroll = log.scroll.saturating_sub(1);
+        }
+    }
+
+    pub fn next_namespace(&mut self) {
+        if let Some(Overlay::Namespaces(picker)) = &mut self.overlay {
+            let selected_namespace = picker.selection.selected().unwrap_or(0);
+            picker.selection.select(Some((selected_namespace + 1) % picker.namespaces.len()));
+        }
+    }
+
+    pub fn previous_namespace(&mut self) {
+        if let Some(Overlay::Namespaces(picker)) = &mut self.overlay {
+            let selected_namespace = picker.selection.selected().unwrap_or(0);
+            picker.selection.select(Some((selected_namespace + picker.namespaces.len() - 1) % picker.namespaces.len()));
+        }
+    }
+
+    /// Closes the namespace picker, listing the services of the selected namespace; forwards keep running.
+    pub fn switch_to_selected_namespace(&mut self) {
+        if let Some(Overlay::Namespaces(NamespacePicker { namespaces, selection })) = self.overlay.take() {
+            if let Some(selected_namespace) = selection.selected() {
+                let namespace_opt = namespaces[selected_namespace].clone();
+                self.all_namespaces = namespace_opt.is_none();
+                self.namespaces = namespace_opt.into_iter().collect();
+                self.service_selection.select(None);
+                self.port_selection.select(None);
+                self.loading = true;
+                if let Some((interval, refresh_task)) = self.refresh_task.take() {
+                    refresh_task.abort();
+                    // Drop what the previous refresh may have fetched for the former namespace
+                    while self.refresh_receiver.try_recv().is_ok() {}
+                    // Only the listing at startup is retried, the namespace being switched to may just not be allowed
+                    self.spawn_service_refresh(interval, 0);
                 }
-            });
-            
-            tokio::spawn(async move {
-                if let Err(e) = port_forwarder.join().await {
-                    println!("Port forwarding for port {} on service {} failed: {}", port, &pod.name(), e);
+            }
+        }
+    }
+
+    /// Applies the most recent background refresh, if any, starting the launch forwards after the first one.
+    pub async fn update_services(&mut self) {
+        let mut latest_listing = None;
+        while let Ok(listing) = self.refresh_receiver.try_recv() {
+            latest_listing = Some(listing);
+        }
+        if let Some(ServiceListing { services, endpoints }) = latest_listing {
+            self.dirty = true;
+            self.credentials_expired = false;
+            self.apply_services(services);
+            self.apply_endpoints(endpoints.unwrap_or_default());
+            self.loading = false;
+            if let Some(LaunchForwards { restore, forwards, selector }) = self.launch_forwards.take() {
+                if restore {
+                    self.restore_session().await;
                 }
-            });
-    
-            let handle_request = |
-                context: Arc<Mutex<hyper::client::conn::SendRequest<hyper::Body>>>,
-                req: Request<Body>| async move {
-                let sender = context.lock();
-                let response = sender.await.send_request(req).await?;
-                Ok(response) as Result<Response<Body>, hyper::Error>
-            };
-            let context = Arc::new(Mutex::new(sender));
-            let make_service = make_service_fn(move |_conn| {
-                let context = context.clone();
-                let service = service_fn(move |req| handle_request(context.clone(), req));
-                async move { Ok::<_, hyper::Error>(service) }
-            });
-    
-            let (sender, mut rx) = tokio::sync::mpsc::channel(1);
-            let addr = SocketAddr::from(([127, 0, 0, 1], port));
-    
-            tokio::spawn(async move {
-                let server = Server::bind(&addr)
-                .serve(make_service)
-                .with_graceful_shutdown(async {
-                    rx.recv().await;
-                });
-    
-                if let Err(e) = server.await {
-                    println!("server error: {}", e);
+                self.forward_at_launch(&forwards).await;
+                if let Some((selector, port)) = selector {
+                    self.forward_by_selector(&selector, port).await;
                 }
-            });
-    
-            return Ok(Some(sender));
+                self.select_first_forwarded_service();
+            }
         }
-    
-        Ok(None)
     }
-}
\ No newline at end of file
+
+    /// Selects the first service with forwards, unless one is already selected, for the launch forwards to show right away.
+    fn select_first_forwarded_service(&mut self) {
+        if self.service_selection.selected().is_some() {
+            return;
+        }
+        let forwarded_service = self.service_list()
+            .iter()
+            .position(|service| self.forwarded_ports.iter().any(|fw_port| fw_port.is_for(service)));
+        if let Some(index) = forwarded_service {
+            self.select_service(index);
+        }
+    }
+
+    /// Replaces the known services, keeping selections and the services that still have active forwards.
+    fn apply_services(&mut self, services: Vec<Service>) {
+        let selected_service = self.service();
+        let (named_services, unnamed_services): (Vec<&Service>, Vec<&Service>) = services
+            .iter()
+            .filter(|svc| self.has_listed_type(svc))
+            .partition(|svc| svc.metadata.name.is_some());
+        if !unnamed_services.is_empty() {
+            self.push_message(StatusMessage::warning(format!("Skipped {} services without a name", unnamed_services.len())));
+        }
+        // Services without ports (e.g. ExternalName ones) are still listed, with no ports to forward.
+        let service_key = |svc: &Service| ServiceKey {
+            namespace: svc.namespace().unwrap_or_else(|| self.default_namespace.clone()),
+            name: svc.name(),
+        };
+        let mut ports_by_service: BTreeMap<ServiceKey, Vec<PortInfo>> = named_services
+            .iter()
+            .map(|svc|
+                (
+                    service_key(svc),
+                    svc.spec
+                        .as_ref()
+                        .and_then(|spec| spec.ports.as_ref())
+                        .map(|ports| ports.iter().map(PortInfo::from_service_port).collect::<Vec<PortInfo>>())
+                        .unwrap_or_default()
+                )
+            )
+            .collect();
+        let mut service_meta: BTreeMap<ServiceKey, ServiceMeta> = named_services
+            .iter()
+            .map(|svc| (service_key(svc), ServiceMeta::from_service(svc)))
+            .collect();
+
+        self.stale_services.clear();
+        for fw_port in &self.forwarded_ports {
+            let service = fw_port.service_key();
+            if !ports_by_service.contains_key(&service) {
+                let ports = self.ports_by_service.get(&service).cloned().unwrap_or_default();
+                ports_by_service.insert(service.clone(), ports);
+                if let Some(meta) = self.service_meta.remove(&service) {
+                    service_meta.insert(service.clone(), meta);
+                }
+                self.stale_services.insert(service);
+            }
+        }
+        self.ports_by_service = ports_by_service;
+        self.service_meta = service_meta;
+        self.reconcile_selection(selected_service);
+    }
+
+    /// Whether `service` is of one of the types given with --service-type, if any.
+    fn has_listed_type(&self, service: &Service) -> bool {
+        self.service_types.is_empty() || self.service_types.iter().any(|service_type| service_type == service_type_of(service))
+    }
+
+    /// Counts the ready addresses of the endpoints matching each known service.
+    fn apply_endpoints(&mut self, endpoints: Vec<Endpoints>) {
+        self.ready_endpoints = endpoints
+            .iter()
+            .map(|endpoints| {
+                let service = ServiceKey {
+                    namespace: endpoints.namespace().unwrap_or_else(|| self.default_namespace.clone()),
+                    name: endpoints.name(),
+                };
+                let ready = endpoints.subsets
+                    .iter()
+                    .flatten()
+                    .map(|subset| subset.addresses.as_ref().map_or(0, Vec::len))
+                    .sum();
+                (service, ready)
+            })
+            .filter(|(service, _)| self.ports_by_service.contains_key(service))
+            .collect();
+    }
+
+    /// Drops the stale services whose forwards have all been stopped.
+    fn prune_stale_services(&mut self) {
+        let selected_service = self.service();
+        let unused_services: Vec<ServiceKey> = self.stale_services
+            .iter()
+            .filter(|service| self.forwarded_ports_for_service(service).is_empty())
+            .cloned()
+            .collect();
+        for service in unused_services {
+            self.stale_services.remove(&service);
+            self.ports_by_service.remove(&service);
+            self.service_meta.remove(&service);
+        }
+        self.reconcile_selection(selected_service);
+    }
+
+    /// Selects `prev_service` again after the service list changed, clamping the port selection;
+    /// when it is gone, the service that took its place is selected, if any, with none of its ports.
+    fn reconcile_selection(&mut self, prev_service: Option<ServiceKey>) {
+        let service_list = self.service_list();
+        let prev_index = self.service_selection.selected();
+        let Some(prev_service) = prev_service else {
+            self.service_selection.select(None);
+            self.port_selection.select(None);
+            return;
+        };
+        if let Some(selected_service) = service_list.iter().position(|svc| svc == &prev_service) {
+            self.service_selection.select(Some(selected_service));
+            let port_list_len = self.port_list().len();
+            match self.port_selection.selected() {
+                Some(_) if port_list_len == 0 => self.port_selection.select(None),
+                Some(selected_port) if selected_port >= port_list_len => self.port_selection.select(Some(port_list_len - 1)),
+                _ => ()
+            }
+        } else {
+            let selected_service = prev_index.filter(|_| !service_list.is_empty()).map(|index| index.min(service_list.len() - 1));
+            self.service_selection.select(selected_service);
+            self.port_selection.select(None);
+            self.port_filter = None;
+        }
+    }
+
+    pub fn push_message(&mut self, message: StatusMessage) {
+        match message.severity {
+            Severity::Info => tracing::info!("{}", message.text),
+            Severity::Warning => tracing::warn!("{}", message.text),
+            Severity::Error => tracing::error!("{}", message.text),
+        }
+        if let Severity::Error = message.severity {
+            if self.errors.len() == MAX_ERRORS {
+                self.errors.pop_front();
+            }
+            self.errors.push_back(StatusMessage { severity: Severity::Error, text: message.text.clone(), created_at: message.created_at });
+        }
+        self.messages.push(message);
+        self.dirty = true;
+    }
+
+    /// Version reported by the API server of the cluster.
+    pub async fn server_version(&self) -> Result<Info, kube::Error> {
+        self.client.server_version().await
+    }
+
+    /// Reloads the client when the credentials were rejected, so that new forwards use renewed ones.
+    pub async fn update_credentials(&mut self) {
+        let mut rejected = false;
+        while self.auth_failure_receiver.try_recv().is_ok() {
+            rejected = true;
+        }
+        if !rejected {
+            return;
+        }
+        if !self.credentials_expired {
+            self.credentials_expired = true;
+            self.dirty = true;
+            tracing::warn!("Credentials rejected by the cluster, reloading them from the kube config");
+        }
+        if let Some(client) = AppState::reload_client(self.kubeconfig_opt.clone(), self.context_opt.clone()).await {
+            self.client = client;
+        }
+    }
+
+    /// Collects messages sent by background tasks and drops the expired ones.
+    pub fn update_messages(&mut self) {
+        while let Ok(message) = self.message_receiver.try_recv() {
+            self.push_message(message);
+        }
+        let message_count = self.messages.len();
+        self.messages.retain(|message| !message.is_expired());
+        if self.messages.len() != message_count {
+            self.dirty = true;
+        }
+    }
+
+    /// Applies the status updates reported by the forwarding tasks.
+    pub fn update_forwards(&mut self) {
+        let mut urls_to_open = vec![];
+        while let Ok(ForwardUpdate { id, change }) = self.forward_update_receiver.try_recv() {
+            self.dirty = true;
+            urls_to_open.extend(self.apply_forward_update(id, change));
+        }
+        for url in urls_to_open {
+            self.open_in_browser(&url);
+        }
+    }
+
+    /// Applies `change` to the forward `id`, returning the URL to open once it's active, if asked to.
+    fn apply_forward_update(&mut self, id: u64, change: ForwardChange) -> Option<String> {
+        let Some(fw_port) = self.forwarded_ports.iter_mut().find(|fw_port| fw_port.id == id) else {
+            // The forwards of a service forwarded at once are only added once set up, having reported already
+            if let Some(early_updates) = self.bulk_forward.as_mut().and_then(|bulk_forward| bulk_forward.early_updates.get_mut(&id)) {
+                early_updates.push(change);
+            }
+            return None;
+        };
+        let status = match change {
+            ForwardChange::Status(status) => status,
+            ForwardChange::Pod { name, node } => {
+                fw_port.pod_name = Some(name);
+                fw_port.node_name = node;
+                return None;
+            }
+            // Stopped forwards are removed before their server exits, and failed ones already say why
+            ForwardChange::ServerExited if matches!(fw_port.status, ForwardStatus::Failed(_)) => return None,
+            ForwardChange::ServerExited => ForwardStatus::Failed("local server exited".to_string()),
+        };
+        match (&fw_port.status, &status) {
+            // A forward that failed while its server was starting stays failed
+            (ForwardStatus::Failed(_), ForwardStatus::Active) => return None,
+            (ForwardStatus::Active, ForwardStatus::Connecting) => {
+                fw_port.last_reconnect = Some(Instant::now());
+                fw_port.reconnects += 1;
+            }
+            _ => (),
+        }
+        let message = match &status {
+            // Rather than the whole service, which it would seem to forward to otherwise
+            ForwardStatus::Active if fw_port.pod.is_none() && self.service_meta.get(&fw_port.service_key()).is_some_and(ServiceMeta::is_headless) => Some(StatusMessage::info(format!(
+                "Forwarding {} -> {}:{} through pod {}, the service being headless",
+                fw_port.local_address(), fw_port.service, fw_port.port, fw_port.pod_name.as_deref().unwrap_or("-")
+            ))),
+            ForwardStatus::Active => Some(StatusMessage::info(format!(
+                "Forwarding {} -> {}:{}", fw_port.local_address(), fw_port.service, fw_port.port
+            ))),
+            ForwardStatus::Failed(reason) => Some(StatusMessage::error(format!(
+                "Forwarding port {} for service {} failed: {}", fw_port.port, fw_port.service, reason
+            ))),
+            ForwardStatus::Connecting => None,
+        };
+        let mut url_to_open = None;
+        match &status {
+            ForwardStatus::Active if self.open_when_active.remove(&id) => {
+                let port = self.ports_by_service
+                    .get(&fw_port.service_key())
+                    .and_then(|ports| ports.iter().find(|port| port.port == fw_port.port as i32));
+                url_to_open = port.map(|port| fw_port.local_url(port));
+            }
+            ForwardStatus::Failed(_) => {
+                self.open_when_active.remove(&id);
+            }
+            _ => (),
+        }
+        fw_port.status = status;
+        if let Some(message) = message {
+            self.push_message(message);
+        }
+        url_to_open
+    }
+
+    pub fn subscribe_forwards(&self) -> watch::Receiver<Vec<ForwardSummary>> {
+        self.forward_summaries.subscribe()
+    }
+
+    /// Publishes the current forwards to the status API, if they changed.
+    pub fn publish_forwards(&self) {
+        let summaries: Vec<ForwardSummary> = self.forwarded_ports.iter().map(ForwardSummary::from).collect();
+        self.forward_summaries.send_if_modified(|published| {
+            if *published == summaries {
+                false
+            } else {
+                *published = summaries;
+                true
+            }
+        });
+    }
+
+    fn forward_reporter(&mut self) -> ForwardReporter {
+        let id = self.next_forward_id;
+        self.next_forward_id += 1;
+        ForwardReporter {
+            id,
+            updates: self.forward_update_sender.clone(),
+            messages: self.message_sender.clone(),
+            auth_failures: self.auth_failure_sender.clone(),
+            traffic: Arc::default(),
+        }
+    }
+
+    pub fn forwarded_ports_for_service(&self, service: &ServiceKey) -> Vec<&ForwardedPort> {
+        self.forwarded_ports.iter().filter(|fw_port| fw_port.is_for(service)).collect()
+    }
+
+    pub fn marked_ports_for_selected_service(&self) -> Vec<i32> {
+        match self.service() {
+            Some(service) => self.marked_ports.iter().filter(|(svc, _)| svc == &service).map(|(_, port)| *port).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Marks the selected port to be toggled with the other marked ones, or unmarks it.
+    pub fn toggle_mark(&mut self) {
+        if let (Some(service), Some(selected_port)) = (self.service(), self.port_selection.selected()) {
+            let mark = (service, self.port_list()[selected_port].port);
+            if !self.marked_ports.remove(&mark) {
+                self.marked_ports.insert(mark);
+            }
+        }
+    }
+
+    pub fn forwarded_ports_for_selected_service(&self) -> Vec<&ForwardedPort> {
+        if let Some(service) = self.service() {
+            self.forwarded_ports_for_service(&service)
+        } else {
+            vec![]
+        }
+    }
+
+    pub fn service(&self) -> Option<ServiceKey> {
+        self.service_selection
+            .selected()
+            .map(|selected_service| self.service_list()[selected_service].clone())
+    }
+
+    /// Services to display, filtered and in the current sort order; `ports_by_service` stays sorted by namespace and name.
+    pub fn service_list(&self) -> Vec<ServiceKey> {
+        let mut service_list: Vec<ServiceKey> = self.ports_by_service
+            .keys()
+            .filter(|service| self.matches_service_filter(service) && self.matches_type_filter(service))
+            .cloned()
+            .collect();
+        match self.sort_mode {
+            SortMode::NameAscending => (),
+            SortMode::NameDescending => service_list.reverse(),
+            // Stable, so that services with as many forwards stay sorted by name
+            SortMode::ActiveForwards => service_list.sort_by_key(|service| std::cmp::Reverse(self.forwarded_ports_for_service(service).len())),
+        }
+        // Listed under a header per namespace, each namespace keeping the sort order
+        if self.multiple_namespaces() {
+            service_list.sort_by(|a, b| a.namespace.cmp(&b.namespace));
+        }
+        service_list
+    }
+
+    /// Switches to the next sort order, keeping the selected service.
+    pub fn cycle_sort_mode(&mut self) {
+        let selected_service = self.service();
+        self.sort_mode = self.sort_mode.next();
+        self.reconcile_selection(selected_service);
+    }
+
+    /// Switches to showing the services of the next type among the listed ones, all of them after the last type.
+    pub fn cycle_type_filter(&mut self) {
+        let selected_service = self.service();
+        let service_types: BTreeSet<&String> = self.ports_by_service
+            .keys()
+            .filter_map(|service| self.service_meta.get(service))
+            .map(|meta| &meta.service_type)
+            .collect();
+        let next_type = match &self.type_filter {
+            Some(type_filter) => service_types.into_iter().find(|service_type| *service_type > type_filter),
+            None => service_types.into_iter().next(),
+        };
+        self.type_filter = next_type.cloned();
+        let text = match &self.type_filter {
+            Some(type_filter) => format!("Showing only the {} services", type_filter),
+            None => "Showing the services of every type".to_string(),
+        };
+        self.push_message(StatusMessage::info(text));
+        self.reconcile_selection(selected_service);
+    }
+
+    fn matches_type_filter(&self, service: &ServiceKey) -> bool {
+        match &self.type_filter {
+            Some(type_filter) => self.service_meta.get(service).is_some_and(|meta| &meta.service_type == type_filter),
+            None => true,
+        }
+    }
+
+    fn matches_service_filter(&self, service: &ServiceKey) -> bool {
+        match &self.service_filter {
+            Some(filter) => self.service_label(service)
+                .to_ascii_lowercase()
+                .contains(&filter.to_ascii_lowercase()),
+            None => true,
+        }
+    }
+
+    /// Namespace the services are listed from, the first one when listing several, unless listing all of them.
+    pub fn namespace(&self) -> &str {
+        self.namespaces.first().map(String::as_str).unwrap_or(&self.default_namespace)
+    }
+
+    /// Whether the services are listed from more than one namespace.
+    pub fn multiple_namespaces(&self) -> bool {
+        self.all_namespaces || self.namespaces.len() > 1
+    }
+
+    /// Namespaces the services are listed from, comma separated, or `all`.
+    pub fn namespaces_label(&self) -> String {
+        if self.all_namespaces {
+            "all".to_string()
+        } else if self.namespaces.is_empty() {
+            self.default_namespace.clone()
+        } else {
+            self.namespaces.join(", ")
+        }
+    }
+
+    /// Name to display for a service, qualified by its namespace when listing several of them.
+    pub fn service_label(&self, service: &ServiceKey) -> String {
+        if self.multiple_namespaces() {
+            format!("{}/{}", service.namespace, service.name)
+        } else {
+            service.name.clone()
+        }
+    }
+
+    /// Whether a service is selected and exposes no ports at all
+    pub fn selected_service_exposes_no_ports(&self) -> bool {
+        self.service().is_some_and(|service| self.ports_by_service[&service].is_empty())
+    }
+
+    /// Ports of the selected service, filtered by number or name.
+    pub fn port_list(&self) -> Vec<PortInfo> {
+        if let Some(service) = self.service() {
+            self.ports_by_service[&service]
+                .iter()
+                .filter(|port| match &self.port_filter {
+                    Some(filter) => port.label().to_ascii_lowercase().contains(&filter.to_ascii_lowercase()),
+                    None => true,
+                })
+                .cloned()
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
+    /// Starts editing the filter of the ports when focused, of the services otherwise.
+    pub fn start_filter(&mut self) {
+        if self.focused_pane() == Pane::Ports {
+            self.filtering = Some(FilterTarget::Ports);
+            self.port_filter.get_or_insert_with(String::new);
+        } else {
+            self.filtering = Some(FilterTarget::Services);
+            self.service_filter.get_or_insert_with(String::new);
+        }
+    }
+
+    pub fn push_filter_char(&mut self, c: char) {
+        self.edit_filter(|filter| filter.push(c));
+    }
+
+    pub fn pop_filter_char(&mut self) {
+        self.edit_filter(|filter| { filter.pop(); });
+    }
+
+    fn edit_filter(&mut self, edit: impl FnOnce(&mut String)) {
+        match self.filtering {
+            Some(FilterTarget::Services) => {
+                if let Some(filter) = &mut self.service_filter {
+                    edit(filter);
+                    self.select_first_service();
+                }
+            }
+            Some(FilterTarget::Ports) => {
+                if let Some(filter) = &mut self.port_filter {
+                    edit(filter);
+                    let first_port = if self.port_list().is_empty() { None } else { Some(0) };
+                    self.port_selection.select(first_port);
+                }
+            }
+            None => (),
+        }
+    }
+
+    /// Stops editing the filter, keeping it applied unless it is empty.
+    pub fn confirm_filter(&mut self) {
+        match self.filtering.take() {
+            Some(FilterTarget::Services) if self.service_filter.as_deref() == Some("") => self.clear_service_filter(),
+            Some(FilterTarget::Ports) if self.port_filter.as_deref() == Some("") => self.clear_port_filter(),
+            _ => (),
+        }
+    }
+
+    /// Removes the filter being typed, or else the filter of the ports, or else the filter of the services.
+    pub fn clear_filter(&mut self) {
+        match self.filtering.take() {
+            Some(FilterTarget::Services) => self.clear_service_filter(),
+            Some(FilterTarget::Ports) => self.clear_port_filter(),
+            None if self.port_filter.is_some() => self.clear_port_filter(),
+            None => self.clear_service_filter(),
+        }
+    }
+
+    /// Removes the filter of the services, keeping the selected service selected in the full list.
+    fn clear_service_filter(&mut self) {
+        let selected_service = self.service();
+        self.service_filter = None;
+        self.reconcile_selection(selected_service);
+    }
+
+    /// Removes the filter of the ports, keeping the selected port selected in the full list.
+    fn clear_port_filter(&mut self) {
+        let selected_port = self.port_selection.selected().map(|selected_port| self.port_list()[selected_port].port);
+        self.port_filter = None;
+        let port_list = self.port_list();
+        let selected_port = selected_port
+            .and_then(|port| port_list.iter().position(|svc_port| svc_port.port == port))
+            .or(if port_list.is_empty() { None } else { Some(0) });
+        self.port_selection.select(selected_port);
+    }
+
+    fn select_first_service(&mut self) {
+        let first_service = if self.service_list().is_empty() { None } else { Some(0) };
+        self.service_selection.select(first_service);
+        self.port_selection.select(None);
+    }
+
+    /// Selects the service at `index` in the services list, leaving its ports.
+    pub fn select_service(&mut self, index: usize) {
+        self.remember_port_selection();
+        self.service_selection.select(Some(index));
+        self.port_selection.select(None);
+        self.port_filter = None;
+        self.focus = Pane::Services;
+    }
+
+    /// Selects the next service with forwards after the selected one, wrapping around.
+    pub fn select_next_forwarded_service(&mut self) {
+        let len = self.service_list().len();
+        let start = self.service_selection.selected().unwrap_or(len.saturating_sub(1));
+        self.select_forwarded_service((1..=len).map(|offset| (start + offset) % len));
+    }
+
+    /// Selects the previous service with forwards before the selected one, wrapping around.
+    pub fn select_previous_forwarded_service(&mut self) {
+        let len = self.service_list().len();
+        let start = self.service_selection.selected().unwrap_or(0);
+        self.select_forwarded_service((1..=len).map(|offset| (start + len - offset) % len));
+    }
+
+    /// Selects the first service with forwards among the services at `indices`.
+    fn select_forwarded_service(&mut self, mut indices: impl Iterator<Item = usize>) {
+        let service_list = self.service_list();
+        match indices.find(|index| !self.forwarded_ports_for_service(&service_list[*index]).is_empty()) {
+            Some(index) => self.select_service(index),
+            None => self.push_message(StatusMessage::info("No service has forwards yet, press Enter on a port to forward it")),
+        }
+    }
+
+    pub fn select_port(&mut self, index: usize) {
+        if self.service_selection.selected().is_some() {
+            self.port_selection.select(Some(index));
+            self.focus = Pane::Ports;
+        }
+    }
+
+    /// Selects the port last selected in the service, or the first one if its ports changed since.
+    pub fn select(&mut self) {
+        let port_list_len = self.port_list().len();
+        if self.port_selection.selected().is_none() && port_list_len > 0 {
+            let last_selected_port = self.service()
+                .and_then(|service| self.last_port_selection.get(&service).copied())
+                .filter(|&selected_port| selected_port < port_list_len)
+                .unwrap_or(0);
+            self.port_selection.select(Some(last_selected_port));
+        }
+        self.focus = Pane::Ports;
+    }
+
+    pub fn deselect(&mut self) {
+        if self.port_selection.selected().is_some() {
+            self.remember_port_selection();
+            self.port_selection.select(None);
+            self.port_filter = None;
+        }
+        self.focus = Pane::Services;
+    }
+
+    /// Pane the navigation keys move in, the ports one only while one of them is selected.
+    pub fn focused_pane(&self) -> Pane {
+        match self.port_selection.selected() {
+            Some(_) => self.focus,
+            None => Pane::Services,
+        }
+    }
+
+    /// Moves the focus to the other pane, keeping the selected port when leaving the ports, selecting one when entering them.
+    pub fn switch_focus(&mut self) {
+        match self.focused_pane() {
+            Pane::Services => self.select(),
+            Pane::Ports => self.focus = Pane::Services,
+        }
+    }
+
+    /// Selects the service at `index` of the services list, which moves away from the selected port, if any.
+    fn move_to_service(&mut self, index: usize) {
+        if self.service_selection.selected() != Some(index) {
+            self.select_service(index);
+        }
+    }
+
+    /// Records the selected port of the selected service, for `select` to return to it.
+    fn remember_port_selection(&mut self) {
+        if let (Some(service), Some(selected_port)) = (self.service(), self.port_selection.selected()) {
+            let port = self.port_list()[selected_port].port;
+            if let Some(index) = self.ports_by_service[&service].iter().position(|svc_port| svc_port.port == port) {
+                self.last_port_selection.insert(service, index);
+            }
+        }
+    }
+
+    // Navigation is a no-op on empty lists, which can happen after a refresh or with a filter.
+    pub fn next(&mut self) {
+        match (self.focused_pane(), self.port_selection.selected(), self.service_selection.selected()) {
+            (Pane::Ports, Some(selected_port), _) => {
+                let port_list_len = self.port_list().len();
+                if port_list_len > 0 {
+                    self.port_selection.select(Some((selected_port + 1) % port_list_len));
+                }
+            }
+            (_, _, Some(selected_service)) => {
+                let svc_list_len = self.service_list().len();
+                if svc_list_len > 0 {
+                    self.move_to_service((selected_service + 1) % svc_list_len);
+                }
+            }
+            (_, _, None) => {
+                if !self.service_list().is_empty() {
+                    self.service_selection.select(Some(0));
+                }
+            }
+        }
+    }
+
+    pub fn previous(&mut self) {
+        match (self.focused_pane(), self.port_selection.selected(), self.service_selection.selected()) {
+            (Pane::Ports, Some(selected_port), _) => {
+                let port_list_len = self.port_list().len();
+                if port_list_len > 0 {
+                    self.port_selection.select(Some((selected_port + port_list_len - 1) % port_list_len));
+                }
+            }
+            (_, _, Some(selected_service)) => {
+                let svc_list_len = self.service_list().len();
+                if svc_list_len > 0 {
+                    self.move_to_service((selected_service + svc_list_len - 1) % svc_list_len);
+                }
+            }
+            (_, _, None) => {
+                if !self.service_list().is_empty() {
+                    self.service_selection.select(Some(self.service_list().len() - 1));
+                }
+            }
+        }
+    }
+
+    /// Lists the pods matching the selector of the selected service, to forward the selected port to one of them.
+    pub async fn open_pod_picker(&mut self) {
+        if let (Some(service), Some(selected_port)) = (self.service(), self.port_selection.selected()) {
+            let port = self.port_list()[selected_port].port as u16;
+            let selector = match self.service_meta[&service].selector_label() {
+                Some(selector) => selector,
+                None => {
+                    self.push_message(StatusMessage::warning(format!("Service {} has no selector to find its pods", service)));
+                    return;
+                }
+            };
+            let pods = match self.client.list_pods(&service.namespace, Some(&selector)).await {
+                Ok(pods) => pods,
+                Err(error) => {
+                    self.push_message(StatusMessage::error(format!("Unable to list the pods of service {}: {}", service, error)));
+                    return;
+                }
+            };
+            // Each pod is followed by the TCP ports of its containers
+            let pods: Vec<PodChoice> = pods
+                .iter()
+                .flat_map(|pod| {
+                    let container_ports: Vec<ContainerPort> = pod.spec
+                        .iter()
+                        .flat_map(|spec| &spec.containers)
+                        .flat_map(|container| container.ports.iter().flatten().map(move |port| (container, port)))
+                        .filter(|(_, port)| port.protocol.as_deref().unwrap_or("TCP") == "TCP")
+                        .map(|(container, port)| ContainerPort { container: container.name.clone(), port: port.container_port as u16, name: port.name.clone() })
+                        .collect();
+                    std::iter::once(None)
+                        .chain(container_ports.into_iter().map(Some))
+                        .map(|container_port| PodChoice { pod: pod.name(), container_port })
+                })
+                .collect();
+            if pods.is_empty() {
+                self.push_message(StatusMessage::warning(format!("No pod matched selector {} of service {}", selector, service)));
+            } else {
+                let mut selection = ListState::default();
+                selection.select(Some(0));
+                self.overlay = Some(Overlay::Pods(PodPicker { service, port, pods, selection }));
+            }
+        }
+    }
+
+    /// Lists the OpenShift routes of the listed namespaces, when enabled with --include-routes, to forward the service port of one of them.
+    pub async fn open_route_picker(&mut self) {
+        if !self.include_routes {
+            self.push_message(StatusMessage::info("Routes aren't listed, run with --include-routes to forward them"));
+            return;
+        }
+        let mut routes = vec![];
+        for namespace in AppState::listed_namespaces(self.client.as_ref(), &self.namespaces, self.all_namespaces) {
+            match self.client.list_routes(namespace.as_deref()).await {
+                Ok(list) => routes.extend(list),
+                // The Route CRD only exists on OpenShift clusters
+                Err(kube::Error::Api(response)) if response.code == 404 => {
+                    self.push_message(StatusMessage::info("No OpenShift routes on this cluster"));
+                    return;
+                }
+                Err(error) => {
+                    self.push_message(StatusMessage::error(format!("Unable to list routes: {}", error)));
+                    return;
+                }
+            }
+        }
+        let routes: Vec<RouteEntry> = routes
+            .iter()
+            .filter_map(|route| {
+                let spec = &route.data["spec"];
+                let service = ServiceKey {
+                    namespace: route.namespace().unwrap_or_else(|| self.namespace().to_owned()),
+                    name: spec["to"]["name"].as_str()?.to_owned(),
+                };
+                let port = self.route_port(&service, &spec["port"]["targetPort"]);
+                Some(RouteEntry { name: route.name(), host: spec["host"].as_str().map(str::to_owned), service, port })
+            })
+            .collect();
+        if routes.is_empty() {
+            self.push_message(StatusMessage::info("No route found"));
+        } else {
+            let mut selection = ListState::default();
+            selection.select(Some(0));
+            self.overlay = Some(Overlay::Routes(RoutePicker { routes, selection }));
+        }
+    }
+
+    /// Service port a route targets, by target port or name, the first TCP port when the route doesn't tell.
+    fn route_port(&self, service: &ServiceKey, target_port: &serde_json::Value) -> Option<u16> {
+        let ports = self.ports_by_service.get(service)?;
+        let port = match target_port {
+            serde_json::Value::Number(number) => {
+                let number = number.as_i64()? as i32;
+                ports.iter().find(|port| port.target_port == Some(IntOrString::Int(number)) || port.port == number)
+            }
+            serde_json::Value::String(name) => ports.iter().find(|port| {
+                port.name.as_ref() == Some(name) || port.target_port == Some(IntOrString::String(name.clone()))
+            }),
+            _ => ports.iter().find(|port| port.protocol == "TCP"),
+        };
+        port.map(|port| port.port as u16)
+    }
+
+    pub fn next_route(&mut self) {
+        if let Some(Overlay::Routes(picker)) = &mut self.overlay {
+            let selected_route = picker.selection.selected().unwrap_or(0);
+            picker.selection.select(Some((selected_route + 1) % picker.routes.len()));
+        }
+    }
+
+    pub fn previous_route(&mut self) {
+        if let Some(Overlay::Routes(picker)) = &mut self.overlay {
+            let selected_route = picker.selection.selected().unwrap_or(0);
+            picker.selection.select(Some((selected_route + picker.routes.len() - 1) % picker.routes.len()));
+        }
+    }
+
+    /// Closes the route picker, forwarding the service port of the selected route.
+    pub async fn forward_selected_route(&mut self) {
+        if let Some(Overlay::Routes(RoutePicker { mut routes, selection })) = self.overlay.take() {
+            if let Some(selected_route) = selection.selected() {
+                let RouteEntry { name, service, port, .. } = routes.swap_remove(selected_route);
+                match port {
+                    Some(port) => self.start_port_forwarding_or_report(&service, None, None, port, port).await,
+                    None => self.push_message(StatusMessage::warning(format!("Route {} doesn't point to a listed port of service {}", name, service))),
+                }
+            }
+        }
+    }
+
+    /// Lists the ports forwarded before, to forward one of them again.
+    pub fn open_history(&mut self) {
+        if self.history.is_empty() {
+            self.push_message(StatusMessage::info("No port forwarded yet"));
+            return;
+        }
+        let mut selection = ListState::default();
+        selection.select(Some(0));
+        self.overlay = Some(Overlay::History(HistoryPicker { selection }));
+    }
+
+    pub fn next_history_entry(&mut self) {
+        if let Some(Overlay::History(picker)) = &mut self.overlay {
+            let selected_entry = picker.selection.selected().unwrap_or(0);
+            picker.selection.select(Some((selected_entry + 1) % self.history.len()));
+        }
+    }
+
+    pub fn previous_history_entry(&mut self) {
+        if let Some(Overlay::History(picker)) = &mut self.overlay {
+            let selected_entry = picker.selection.selected().unwrap_or(0);
+            picker.selection.select(Some((selected_entry + self.history.len() - 1) % self.history.len()));
+        }
+    }
+
+    /// Closes the history, forwarding the selected port again unless it still is.
+    pub async fn forward_selected_history_entry(&mut self) {
+        let Some(Overlay::History(HistoryPicker { selection })) = self.overlay.take() else {
+            return;
+        };
+        let Some(HistoryEntry { namespace, service, remote_port, local_port }) = selection.selected().and_then(|selected| self.history.get(selected).cloned()) else {
+            return;
+        };
+        let service = ServiceKey { namespace, name: service };
+        if !self.exposes_port(&service, remote_port) {
+            self.push_message(StatusMessage::warning(format!("Unable to forward port {} of service {}, it isn't listed", remote_port, service)));
+            return;
+        }
+        if self.forwarded_ports.iter().any(|fw_port| fw_port.is_for(&service) && fw_port.port == remote_port && fw_port.local_port == local_port) {
+            self.push_message(StatusMessage::info(format!("Port {} of service {} is already forwarded to {}", remote_port, service, local_port)));
+            return;
+        }
+        self.start_port_forwarding_or_report(&service, None, None, remote_port, local_port).await;
+    }
+
+    pub fn next_pod(&mut self) {
+        if let Some(Overlay::Pods(picker)) = &mut self.overlay {
+            let selected_pod = picker.selection.selected().unwrap_or(0);
+            picker.selection.select(Some((selected_pod + 1) % picker.pods.len()));
+        }
+    }
+
+    pub fn previous_pod(&mut self) {
+        if let Some(Overlay::Pods(picker)) = &mut self.overlay {
+            let selected_pod = picker.selection.selected().unwrap_or(0);
+            picker.selection.select(Some((selected_pod + picker.pods.len() - 1) % picker.pods.len()));
+        }
+    }
+
+    /// Closes the pod picker, forwarding the port, or the selected container port, to the selected pod.
+    pub async fn forward_to_selected_pod(&mut self) {
+        if let Some(Overlay::Pods(PodPicker { service, port, mut pods, selection })) = self.overlay.take() {
+            if let Some(selected_pod) = selection.selected() {
+                let PodChoice { pod, container_port } = pods.swap_remove(selected_pod);
+                self.start_port_forwarding_or_report(&service, Some(pod), container_port, port, port).await;
+            }
+        }
+    }
+
+    pub fn page_down(&mut self, page: usize) {
+        self.move_selection(|selected, len| selected.saturating_add(page).min(len - 1));
+    }
+
+    pub fn page_up(&mut self, page: usize) {
+        self.move_selection(|selected, _| selected.saturating_sub(page));
+    }
+
+    pub fn select_first(&mut self) {
+        self.move_selection(|_, _| 0);
+    }
+
+    pub fn select_last(&mut self) {
+        self.move_selection(|_, len| len - 1);
+    }
+
+    /// Moves the selection of the ports when focused, of the services otherwise; `to` is only called on non-empty lists.
+    fn move_selection(&mut self, to: impl Fn(usize, usize) -> usize) {
+        match (self.focused_pane(), self.port_selection.selected()) {
+            (Pane::Ports, Some(selected_port)) => {
+                let port_list_len = self.port_list().len();
+                if port_list_len > 0 {
+                    self.port_selection.select(Some(to(selected_port, port_list_len)));
+                }
+            }
+            _ => {
+                let svc_list_len = self.service_list().len();
+                if svc_list_len > 0 {
+                    let selected_service = self.service_selection.selected().unwrap_or(0);
+                    self.move_to_service(to(selected_service, svc_list_len));
+                }
+            }
+        }
+    }
+
+    /// Opens the local port prompt for the selected port, unless it is already forwarded.
+    pub fn open_local_port_prompt(&mut self) {
+        if let (Some(service), Some(selected_port)) = (self.service(), self.port_selection.selected()) {
+            let port = self.port_list()[selected_port].port as u16;
+            let already_forwarded = self.forwarded_ports_for_service(&service)
+                .iter()
+                .any(|fw_port| fw_port.port == port);
+            if !already_forwarded {
+                self.prompt = Some(Prompt { kind: PromptKind::LocalPort { service, port }, input: port.to_string() });
+            }
+        }
+    }
+
+    /// Opens the prompt for a range of ports of the selected service to forward.
+    pub fn open_port_range_prompt(&mut self) {
+        if let Some(service) = self.service() {
+            self.prompt = Some(Prompt { kind: PromptKind::PortRange { service }, input: String::new() });
+        }
+    }
+
+    pub fn close_prompt(&mut self) {
+        self.prompt = None;
+    }
+
+    pub async fn submit_prompt(&mut self) {
+        if let Some(Prompt { kind, input }) = &self.prompt {
+            match kind {
+                PromptKind::LocalPort { service, port } => {
+                    if let Ok(local_port @ 1..) = input.parse::<u16>() {
+                        let (service, port) = (service.clone(), *port);
+                        self.prompt = None;
+                        self.start_port_forwarding_or_report(&service, None, None, port, local_port).await;
+                    } else {
+                        let text = format!("'{}' is not a valid local port", input);
+                        self.push_message(StatusMessage::warning(text));
+                    }
+                }
+                PromptKind::PortRange { service } => {
+                    if let Some(range) = parse_port_range(input) {
+                        let service = service.clone();
+                        self.prompt = None;
+                        self.forward_port_range(&service, range).await;
+                    } else {
+                        let text = format!("'{}' is not a valid port range, expected START-END", input);
+                        self.push_message(StatusMessage::warning(text));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Forwarded port currently highlighted in the ports list, if any.
+    pub fn selected_forwarded_port(&self) -> Option<&ForwardedPort> {
+        let service = self.service()?;
+        let port = self.port_list()[self.port_selection.selected()?].port as u16;
+        self.forwarded_ports_for_service(&service)
+            .into_iter()
+            .find(|fw_port| fw_port.port == port)
+    }
+
+    pub fn bind_address(&self) -> IpAddr {
+        self.forward_options.bind_address
+    }
+
+    /// Copies the local address of the selected forwarded port to the clipboard, as a URL for HTTP-looking ports.
+    pub fn copy_selected_address(&mut self) {
+        let port = self.port_selection.selected().map(|selected_port| self.port_list()[selected_port].clone());
+        match self.selected_forwarded_port().zip(port).map(|(fw_port, port)| fw_port.local_url(&port)) {
+            Some(address) => self.copy_to_clipboard(address),
+            None => self.push_message(StatusMessage::info("Select a forwarded port to copy its address")),
+        }
+    }
+
+    /// Copies the `kubectl port-forward` command equivalent to forwarding the selected port, to the local port it's forwarded to if it is.
+    pub fn copy_selected_kubectl_command(&mut self) {
+        let (Some(service), Some(selected_port)) = (self.service(), self.port_selection.selected()) else {
+            self.push_message(StatusMessage::info("Select a port to copy the kubectl command forwarding it"));
+            return;
+        };
+        let port = self.port_list()[selected_port].port as u16;
+        let local_port = self.selected_forwarded_port().map_or(port, |fw_port| fw_port.local_port);
+        let mut command = format!("kubectl port-forward svc/{} {}:{} -n {}", service.name, local_port, port, service.namespace);
+        // kubectl binds to localhost by default
+        if !self.bind_address().is_loopback() {
+            command.push_str(&format!(" --address {}", self.bind_address()));
+        }
+        self.copy_to_clipboard(command);
+    }
+
+    /// Opens the selected HTTP-looking port in the browser, forwarding it first when it isn't and waiting for it to be active.
+    pub async fn open_selected_in_browser(&mut self) {
+        let (Some(service), Some(selected_port)) = (self.service(), self.port_selection.selected()) else {
+            self.push_message(StatusMessage::info("Select an HTTP port to open it in the browser"));
+            return;
+        };
+        let port = self.port_list()[selected_port].clone();
+        if port.url_scheme().is_none() {
+            self.push_message(StatusMessage::info(format!(
+                "Port {} of service {} doesn't look like HTTP: only ports named http or https, or numbered 80, 443 or 8080, are opened", port.port, service
+            )));
+            return;
+        }
+        if self.selected_forwarded_port().is_none() {
+            let selected_service = self.service();
+            self.start_port_forwarding_or_report(&service, None, None, port.port as u16, port.port as u16).await;
+            self.reconcile_selection(selected_service);
+        }
+        match self.selected_forwarded_port() {
+            Some(fw_port) if matches!(fw_port.status, ForwardStatus::Active) => {
+                let url = fw_port.local_url(&port);
+                self.open_in_browser(&url);
+            }
+            Some(fw_port) => {
+                let id = fw_port.id;
+                self.open_when_active.insert(id);
+                self.push_message(StatusMessage::info(format!("Opening port {} of service {} in the browser once forwarded", port.port, service)));
+            }
+            // Forwarding failed, which was already reported
+            None => (),
+        }
+    }
+
+    fn open_in_browser(&mut self, url: &str) {
+        match open::that_detached(url) {
+            Ok(()) => self.push_message(StatusMessage::info(format!("Opened {} in the browser", url))),
+            Err(error) => self.push_message(StatusMessage::error(format!("Unable to open {} in the browser: {}", url, error))),
+        }
+    }
+
+    fn copy_to_clipboard(&mut self, text: String) {
+        // The clipboard is kept around, as on some platforms its contents are lost once it's dropped
+        if self.clipboard.is_none() {
+            match Clipboard::new() {
+                Ok(clipboard) => self.clipboard = Some(clipboard),
+                Err(error) => {
+                    self.push_message(StatusMessage::error(format!("Unable to access the clipboard: {}", error)));
+                    return;
+                }
+            }
+        }
+        let result = self.clipboard.as_mut().map(|clipboard| clipboard.set_text(text.clone()));
+        match result {
+            Some(Ok(())) => self.push_message(StatusMessage::info(format!("Copied {} to the clipboard", text))),
+            Some(Err(error)) => self.push_message(StatusMessage::error(format!("Unable to copy to the clipboard: {}", error))),
+            None => (),
+        }
+    }
+
+    /// Re-establishes the forwards saved by the previous session.
+    pub async fn restore_session(&mut self) {
+        let entries = match session::load() {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                self.push_message(StatusMessage::info("No previous session to restore"));
+                return;
+            }
+            Err(error) => {
+                self.push_message(StatusMessage::warning(format!("Unable to read the previous session: {}", error)));
+                return;
+            }
+        };
+        for SessionEntry { namespace, service, pod, container, container_port, remote_port, local_port } in entries {
+            let container_port = container.zip(container_port).map(|(container, port)| ContainerPort { container, port, name: None });
+            let service = ServiceKey { namespace, name: service };
+            if !self.exposes_port(&service, remote_port) {
+                let text = format!("Skipped restoring port {} for service {}/{}, it no longer exists", remote_port, service.namespace, service.name);
+                self.push_message(StatusMessage::warning(text));
+                continue;
+            }
+            self.start_port_forwarding_or_report(&service, pod, container_port, remote_port, local_port).await;
+        }
+    }
+
+    /// Forwards `port` of every service matching `selector`, each to the first local port from `port` on that is free.
+    async fn forward_by_selector(&mut self, selector: &str, port: u16) {
+        let services = match AppState::get_services(self.client.as_ref(), &self.namespaces, self.all_namespaces, Some(selector)).await {
+            Ok(ServiceListing { services, .. }) => services,
+            Err(error) => {
+                self.push_message(StatusMessage::error(format!("Unable to list the services matching {}: {}", selector, error)));
+                return;
+            }
+        };
+        let services: Vec<ServiceKey> = services
+            .iter()
+            .filter_map(|svc| Some(ServiceKey { namespace: svc.namespace().unwrap_or_else(|| self.default_namespace.clone()), name: svc.metadata.name.clone()? }))
+            .collect();
+        if services.is_empty() {
+            self.push_message(StatusMessage::warning(format!("No service matches {}", selector)));
+        }
+        for service in services {
+            if !self.exposes_tcp_port(&service, port) {
+                self.push_message(StatusMessage::warning(format!("Service {} doesn't expose TCP port {}", service, port)));
+                continue;
+            }
+            match self.free_local_port(port) {
+                Some(local_port) => self.start_port_forwarding_or_report(&service, None, None, port, local_port).await,
+                None => self.push_message(StatusMessage::error(format!("No free local port to forward port {} of service {}", port, service))),
+            }
+        }
+    }
+
+    /// First local port from `from` on that neither a forward uses nor anything else is bound to.
+    fn free_local_port(&self, from: u16) -> Option<u16> {
+        // Forwards bind their port in the background, so the ones just started may not be bound yet
+        (from..=u16::MAX).find(|local_port| {
+            !self.forwarded_ports.iter().any(|fw_port| fw_port.local_port == *local_port)
+                && AppState::probe_local_port(&self.forward_options, *local_port).is_ok()
+        })
+    }
+
+    /// Starts the forwards requested with `--forward`.
+    pub async fn forward_at_launch(&mut self, forwards: &[ForwardSpec]) {
+        for ForwardSpec { service, remote_port, local_port } in forwards {
+            let matches = self.find_services(service);
+            let service = match &matches[..] {
+                [found] if self.exposes_port(found, *remote_port) => found.clone(),
+                [_, _, ..] => {
+                    let namespaces: Vec<&str> = matches.iter().map(|found| found.namespace.as_str()).collect();
+                    let text = format!("Unable to forward {}:{}, a service of that name exists in {}, use NAMESPACE/SERVICE", service, remote_port, namespaces.join(", "));
+                    self.push_message(StatusMessage::warning(text));
+                    continue;
+                }
+                _ => {
+                    let text = format!("Unable to forward {}:{}, no such service or port", service, remote_port);
+                    self.push_message(StatusMessage::warning(text));
+                    continue;
+                }
+            };
+            self.start_port_forwarding_or_report(&service, None, None, *remote_port, local_port.unwrap_or(*remote_port)).await;
+        }
+    }
+
+    /// Services named `service`, which may be qualified by its namespace as `NAMESPACE/SERVICE`.
+    fn find_services(&self, service: &str) -> Vec<ServiceKey> {
+        self.ports_by_service
+            .keys()
+            .filter(|key| match service.split_once('/') {
+                Some((namespace, name)) => key.namespace == namespace && key.name == name,
+                None => key.name == service,
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn exposes_port(&self, service: &ServiceKey, port: u16) -> bool {
+        self.ports_by_service
+            .get(service)
+            .is_some_and(|ports| ports.iter().any(|svc_port| svc_port.port == port as i32))
+    }
+
+    /// A service may expose the same port number over several protocols, e.g. DNS over TCP and UDP.
+    fn exposes_tcp_port(&self, service: &ServiceKey, port: u16) -> bool {
+        self.ports_by_service
+            .get(service)
+            .is_some_and(|ports| ports.iter().any(|svc_port| svc_port.port == port as i32 && svc_port.is_tcp()))
+    }
+
+    async fn start_port_forwarding_or_report(&mut self, service: &ServiceKey, pod: Option<String>, container_port: Option<ContainerPort>, port: u16, local_port: u16) {
+        match self.start_port_forwarding(service, pod, container_port, port, local_port).await {
+            Ok(()) => (),
+            Err(PortnordError::Forward(text)) => self.push_message(StatusMessage::error(text)),
+            Err(error) => {
+                if let PortnordError::Kube(error) = &error {
+                    if is_unauthorized(error) {
+                        let _ = self.auth_failure_sender.send(());
+                    }
+                }
+                let text = format!("An error occurred forwarding port {} for service {}: {}", port, service, error);
+                self.push_message(StatusMessage::error(text));
+            }
+        }
+    }
+
+    /// Saves the current forwards, so that they can be restored on the next launch; simulated forwards are not saved.
+    pub fn save_session(&self) -> std::io::Result<()> {
+        if self.forward_options.dry_run {
+            return Ok(());
+        }
+        let entries: Vec<SessionEntry> = self.forwarded_ports
+            .iter()
+            .map(|fw_port| SessionEntry {
+                namespace: fw_port.namespace.clone(),
+                service: fw_port.service.clone(),
+                pod: fw_port.pod.clone(),
+                container: fw_port.container_port.as_ref().map(|container_port| container_port.container.clone()),
+                container_port: fw_port.container_port.as_ref().map(|container_port| container_port.port),
+                remote_port: fw_port.port,
+                local_port: fw_port.local_port,
+            })
+            .collect();
+        session::save(&entries)
+    }
+
+    /// Forwards `port` of `service`, or `container_port` of `pod` instead when given.
+    async fn start_port_forwarding(&mut self, service: &ServiceKey, pod: Option<String>, container_port: Option<ContainerPort>, port: u16, local_port: u16) -> Result<(), PortnordError> {
+        if !self.exposes_tcp_port(service, port) {
+            let text = format!("Unable to forward port {} for service {}, only TCP ports can be forwarded", port, service);
+            return Err(PortnordError::Forward(text));
+        }
+        // Starting again before the previous server exited would leave two servers behind the same port
+        if self.is_stopping(service, port, local_port) {
+            return Err(PortnordError::Forward(AppState::stopping_text(service, port)));
+        }
+        AppState::probe_local_port(&self.forward_options, local_port).map_err(PortnordError::Forward)?;
+        let namespace = service.namespace.clone();
+        let reporter = self.forward_reporter();
+        let id = reporter.id;
+        let traffic = reporter.traffic.clone();
+        let remote_port = match &container_port {
+            Some(container_port) => RemotePort::Number(container_port.port),
+            None => self.remote_port(service, port),
+        };
+        let pods = match &pod {
+            Some(pod) => PodSelector::Name(pod.clone()),
+            None => self.pod_selector(service),
+        };
+        if let Some(sender) = AppState::run_port_forward(&self.client, &namespace, &service.name, &pods, &remote_port, local_port, &self.forward_options, reporter).await? {
+            let selected_service = self.service();
+            let status = ForwardStatus::Connecting;
+            let bind_address = self.forward_options.bind_address;
+            if pod.is_none() {
+                self.record_history(HistoryEntry { namespace: namespace.clone(), service: service.name.clone(), remote_port: port, local_port });
+            }
+            self.forwarded_ports.push(ForwardedPort { id, namespace, service: service.name.clone(), pod, pod_name: None, node_name: None, container_port, port, bind_address, local_port, status, started_at: Instant::now(), last_reconnect: None, reconnects: 0, traffic, sender });
+            self.reconcile_selection(selected_service);
+        } else {
+            self.push_message(StatusMessage::warning(self.no_pod_text(service)));
+        }
+        Ok(())
+    }
+
+    /// Moves `entry` to the front of the history, saving it; forwards to a specific pod and simulated ones are left out.
+    fn record_history(&mut self, entry: HistoryEntry) {
+        if self.forward_options.dry_run {
+            return;
+        }
+        self.history.retain(|recorded| recorded != &entry);
+        self.history.push_front(entry);
+        self.history.truncate(MAX_HISTORY);
+        if let Err(error) = session::save_history(self.history.make_contiguous()) {
+            self.push_message(StatusMessage::warning(format!("Unable to save the history of forwards: {}", error)));
+        }
+    }
+
+    /// Port of the pods `port` of `service` sends traffic to; the port itself for services not listed (anymore).
+    fn remote_port(&self, service: &ServiceKey, port: u16) -> RemotePort {
+        self.ports_by_service
+            .get(service)
+            .and_then(|ports| ports.iter().find(|port_info| port_info.port == port as i32))
+            .map_or(RemotePort::Number(port), RemotePort::of)
+    }
+
+    /// Pods the forwards of `service` pick one of, those matching its selector.
+    fn pod_selector(&self, service: &ServiceKey) -> PodSelector {
+        PodSelector::Labels(self.service_meta.get(service).and_then(ServiceMeta::selector_label))
+    }
+
+    /// Reports that no pod was found for `service`, with its selector to spot mislabeled pods.
+    fn no_pod_text(&self, service: &ServiceKey) -> String {
+        match self.service_meta.get(service).and_then(ServiceMeta::selector_label) {
+            Some(selector) => format!("No pod found for service {}, whose selector is {}", service, selector),
+            None => format!("No pod found for service {}, which has no selector", service),
+        }
+    }
+
+    /// Stops every forward, reporting how many were stopped.
+    pub async fn stop_all_forwards(&mut self) {
+        let selected_service = self.service();
+        let stopped = self.stop_forwards(|_| true).await;
+        self.reconcile_selection(selected_service);
+        if stopped > 0 {
+            self.push_message(StatusMessage::info(format!("Stopped {} forwards", stopped)));
+        }
+    }
+
+    /// Stops every forward on quit, waiting for their servers to exit, so that the HTTP requests in flight can complete.
+    pub async fn shutdown(&mut self) {
+        self.stop_forwards(|_| true).await;
+        let exited = futures::future::join_all(self.stopping_forwards.iter().map(|fw_port| fw_port.sender.closed()));
+        if tokio::time::timeout(self.forward_options.shutdown_timeout, exited).await.is_err() {
+            tracing::warn!("Gave up waiting for the forwards to stop after {}s", self.forward_options.shutdown_timeout.as_secs());
+        }
+    }
+
+    /// Stops the selected forward and starts it again with the same ports once its server exited,
+    /// connecting to a fresh pod, e.g. after a deployment replaced them; see `update_restarts`.
+    pub async fn restart_selected_forward(&mut self) {
+        let Some(id) = self.selected_forwarded_port().map(|fw_port| fw_port.id) else {
+            self.push_message(StatusMessage::info("Select a forwarded port to restart it"));
+            return;
+        };
+        let selected_service = self.service();
+        let fw_port_idx = self.forwarded_ports.iter().position(|fw_port| fw_port.id == id).unwrap();
+        let fw_port = self.forwarded_ports.remove(fw_port_idx);
+        let _ = fw_port.sender.send(()).await;
+        let restart = Restart {
+            service: fw_port.service_key(),
+            pod: fw_port.pod.clone(),
+            container_port: fw_port.container_port.clone(),
+            port: fw_port.port,
+            local_port: fw_port.local_port,
+            exited: false,
+        };
+        // Its local port is only free again once the server exited, which may take until the requests in flight complete
+        let (sender, restart_sender, shutdown_timeout) = (fw_port.sender.clone(), self.restart_sender.clone(), self.forward_options.shutdown_timeout);
+        tokio::spawn(async move {
+            let exited = tokio::time::timeout(shutdown_timeout, sender.closed()).await.is_ok();
+            let _ = restart_sender.send(Restart { exited, ..restart });
+        });
+        self.push_message(StatusMessage::info(format!("Restarting port {} of service {}…", fw_port.port, fw_port.service)));
+        self.stopping_forwards.push(fw_port);
+        self.reconcile_selection(selected_service);
+    }
+
+    /// Starts the restarted forwards again once the server of their previous run exited.
+    pub async fn update_restarts(&mut self) {
+        while let Ok(Restart { service, pod, container_port, port, local_port, exited }) = self.restart_receiver.try_recv() {
+            self.dirty = true;
+            if !exited {
+                let text = format!("Unable to restart port {} of service {}, its server didn't stop within {}s", port, service, self.forward_options.shutdown_timeout.as_secs());
+                self.push_message(StatusMessage::error(text));
+                continue;
+            }
+            // Sorting by active forwards may move the selected service around
+            let selected_service = self.service();
+            self.start_port_forwarding_or_report(&service, pod, container_port, port, local_port).await;
+            self.reconcile_selection(selected_service);
+        }
+    }
+
+    /// Signals the servers of the forwards matching `filter` to shut down and removes them, returning how many there were.
+    async fn stop_forwards(&mut self, filter: impl Fn(&ForwardedPort) -> bool) -> usize {
+        let (stopped_ports, forwarded_ports): (Vec<ForwardedPort>, Vec<ForwardedPort>) = self.forwarded_ports
+            .drain(..)
+            .partition(|port| filter(port));
+        self.forwarded_ports = forwarded_ports;
+        // The server of a failed forward may already be gone, in which case there is nothing to stop
+        let stopped_ports_futs: Vec<_> = stopped_ports.iter().map(|port| port.sender.send(())).collect();
+        futures::future::join_all(stopped_ports_futs).await;
+        self.prune_stale_services();
+        let stopped = stopped_ports.len();
+        self.stopping_forwards.extend(stopped_ports);
+        stopped
+    }
+
+    /// Whether a stopped forward of `port` of `service`, or on `local_port`, still has its local server running.
+    fn is_stopping(&mut self, service: &ServiceKey, port: u16, local_port: u16) -> bool {
+        // The server drops its end of the stop channel once it exits
+        self.stopping_forwards.retain(|fw_port| !fw_port.sender.is_closed());
+        self.stopping_forwards.iter().any(|fw_port| (fw_port.is_for(service) && fw_port.port == port) || fw_port.local_port == local_port)
+    }
+
+    fn stopping_text(service: &ServiceKey, port: u16) -> String {
+        format!("Port {} of service {} is still being stopped, try again in a moment", port, service)
+    }
+
+    /// Errors are reported in the messages pane, so that a failed forward doesn't end the session.
+    pub async fn toggle_port_forwarding(&mut self) {
+        // Sorting by active forwards may move the selected service around
+        let selected_service = self.service();
+        if self.marked_ports.is_empty() {
+            self.toggle_selected_port_forwarding().await;
+        } else {
+            self.toggle_marked_port_forwarding().await;
+        }
+        self.reconcile_selection(selected_service);
+    }
+
+    /// Stops the marked ports that are forwarded and forwards the others, clearing the marks.
+    async fn toggle_marked_port_forwarding(&mut self) {
+        for (service, port) in std::mem::take(&mut self.marked_ports) {
+            let port = port as u16;
+            if let Some(fw_port_idx) = self.forwarded_ports.iter().position(|fw_port| fw_port.is_for(&service) && fw_port.port == port) {
+                let fw_port = self.forwarded_ports.remove(fw_port_idx);
+                let _ = fw_port.sender.send(()).await;
+                self.stopping_forwards.push(fw_port);
+                self.push_message(StatusMessage::info(format!("Stopped forwarding port {} for service {}", port, service)));
+            } else {
+                self.start_port_forwarding_or_report(&service, None, None, port, port).await;
+            }
+        }
+        self.prune_stale_services();
+    }
+
+    async fn toggle_selected_port_forwarding(&mut self) {
+        if self.service_selection.selected().is_none() {
+            return;
+        }
+        // With the services focused, all the ports of the selected service are toggled, even if one of them is selected
+        if let Some(selected_port) = self.port_selection.selected().filter(|_| self.focused_pane() == Pane::Ports) {
+            let selected_svc = &self.service_list()[self.service_selection.selected().unwrap()];
+            let selected_port = self.port_list()[selected_port].port as u16;
+            let forwarded_ports = &mut self.forwarded_ports;
+            if let Some(existing_forwarded_port_idx) = forwarded_ports.iter().position(|port| {
+                port.is_for(selected_svc) && port.port == selected_port
+            }) {
+                // The server of a failed forward may already be gone, in which case there is nothing to stop
+                let existing_forwarded_port = forwarded_ports.remove(existing_forwarded_port_idx);
+                let _ = existing_forwarded_port.sender.send(()).await;
+                self.stopping_forwards.push(existing_forwarded_port);
+                self.prune_stale_services();
+                self.push_message(StatusMessage::info(format!("Stopped forwarding port {} for service {}", selected_port, selected_svc)));
+            } else {
+                self.start_port_forwarding_or_report(selected_svc, None, None, selected_port, selected_port).await;
+            }
+        } else {
+            let selected_svc = &self.service_list()[self.service_selection.selected().unwrap()];
+            if self.ports_by_service[selected_svc].is_empty() {
+                self.push_message(StatusMessage::warning(format!("Service {} exposes no ports", selected_svc)));
+                return;
+            }
+            let (all_svc_ports, other_svc_ports): (Vec<PortInfo>, Vec<PortInfo>) = self.ports_by_service[selected_svc]
+                .iter()
+                .cloned()
+                .partition(PortInfo::is_tcp);
+            if !other_svc_ports.is_empty() {
+                self.push_message(StatusMessage::warning(format!(
+                    "Skipped {} ports of service {}, only TCP ports can be forwarded", other_svc_ports.len(), selected_svc
+                )));
+            }
+            if all_svc_ports.is_empty() {
+                return;
+            }
+            let svc_forwarded_ports = self.forwarded_ports_for_selected_service();
+            let should_stop_port_forwarding = all_svc_ports.len() == svc_forwarded_ports.len();
+
+            if should_stop_port_forwarding {
+                self.stop_forwards(|port| port.is_for(selected_svc)).await;
+                self.push_message(StatusMessage::info(format!("Stopped forwarding all ports for service {}", selected_svc)));
+            } else {
+                let ports: Vec<u16> = all_svc_ports
+                    .iter()
+                    .map(|port| port.port as u16)
+                    .filter(|port| !svc_forwarded_ports.iter().any(|fw_port| fw_port.port == *port))
+                    .collect();
+                if self.forward_options.confirm_forward_all {
+                    let (service, bind_address) = (selected_svc.clone(), self.forward_options.bind_address);
+                    self.overlay = Some(Overlay::ForwardAll(ForwardAllConfirmation { service, ports, bind_address }));
+                } else {
+                    self.forward_all_ports(selected_svc, ports);
+                }
+            }
+        }
+    }
+
+    /// Forwards each TCP port of `service` within `range` that isn't forwarded yet, to the same local port.
+    async fn forward_port_range(&mut self, service: &ServiceKey, range: RangeInclusive<u16>) {
+        let forwarded: Vec<u16> = self.forwarded_ports_for_service(service).iter().map(|fw_port| fw_port.port).collect();
+        let ports: Vec<u16> = self.ports_by_service[service]
+            .iter()
+            .filter(|port| port.is_tcp())
+            .filter_map(|port| u16::try_from(port.port).ok())
+            .filter(|port| range.contains(port) && !forwarded.contains(port))
+            .collect();
+        if ports.is_empty() {
+            let text = format!("Service {} exposes no TCP port to forward in {}-{}", service, range.start(), range.end());
+            self.push_message(StatusMessage::warning(text));
+            return;
+        }
+        let selected_service = self.service();
+        for port in &ports {
+            self.start_port_forwarding_or_report(service, None, None, *port, *port).await;
+        }
+        self.reconcile_selection(selected_service);
+        let started: Vec<String> = ports
+            .iter()
+            .filter(|port| self.forwarded_ports.iter().any(|fw_port| fw_port.is_for(service) && fw_port.port == **port))
+            .map(u16::to_string)
+            .collect();
+        if !started.is_empty() {
+            let text = format!("Forwarding ports {} of service {}", started.join(", "), service);
+            self.push_message(StatusMessage::info(text));
+        }
+    }
+
+    /// Closes the confirmation, forwarding the ports it listed.
+    pub async fn confirm_forward_all(&mut self) {
+        if let Some(Overlay::ForwardAll(ForwardAllConfirmation { service, ports, .. })) = self.overlay.take() {
+            // Sorting by active forwards may move the selected service around
+            let selected_service = self.service();
+            self.forward_all_ports(&service, ports);
+            self.reconcile_selection(selected_service);
+        }
+    }
+
+    /// Forwards the given ports of `service` concurrently in the background, each to the same local port; see `update_bulk_forwards`.
+    fn forward_all_ports(&mut self, selected_svc: &ServiceKey, ports: Vec<u16>) {
+        if let Some(bulk_forward) = &self.bulk_forward {
+            let text = format!("Still forwarding the ports of service {}, try again once done", bulk_forward.service);
+            self.push_message(StatusMessage::warning(text));
+            return;
+        }
+        let (ports, stopping_ports): (Vec<u16>, Vec<u16>) = ports.into_iter().partition(|port| !self.is_stopping(selected_svc, *port, *port));
+        for port in stopping_ports {
+            self.push_message(StatusMessage::warning(AppState::stopping_text(selected_svc, port)));
+        }
+        if ports.is_empty() {
+            return;
+        }
+        let reporters: Vec<ForwardReporter> = (0..ports.len()).map(|_| self.forward_reporter()).collect();
+        let early_updates = reporters.iter().map(|reporter| (reporter.id, vec![])).collect();
+        self.bulk_forward = Some(BulkForward { service: selected_svc.clone(), done: 0, total: ports.len(), early_updates });
+        let remote_ports: Vec<RemotePort> = ports.iter().map(|port| self.remote_port(selected_svc, *port)).collect();
+        let no_pod_text = self.no_pod_text(selected_svc);
+        let pods = self.pod_selector(selected_svc);
+        let (client, forward_options, selected_svc) = (self.client.clone(), self.forward_options.clone(), selected_svc.clone());
+        let message_sender = self.message_sender.clone();
+        let auth_failure_sender = self.auth_failure_sender.clone();
+        let bulk_forward_sender = self.bulk_forward_sender.clone();
+        tokio::spawn(async move {
+            let (client, forward_options, selected_svc, no_pod_text, pods) = (&client, &forward_options, &selected_svc, &no_pod_text, &pods);
+            let (message_sender, auth_failure_sender, bulk_forward_sender) = (&message_sender, &auth_failure_sender, &bulk_forward_sender);
+            let namespace = &selected_svc.namespace;
+            let forwarded_ports_futs: Vec<_> = ports.into_iter().zip(remote_ports).zip(reporters).map(|((port, remote_port), reporter)| async move {
+                let id = reporter.id;
+                let traffic = reporter.traffic.clone();
+                if let Err(text) = AppState::probe_local_port(forward_options, port) {
+                    let _ = message_sender.send(StatusMessage::error(text));
+                    let _ = bulk_forward_sender.send(None);
+                    return;
+                }
+                let forwarded_port = match AppState::run_port_forward(client, namespace, &selected_svc.name, pods, &remote_port, port, forward_options, reporter).await {
+                    Ok(Some(sender)) => {
+                        let status = ForwardStatus::Connecting;
+                        let bind_address = forward_options.bind_address;
+                        Some(ForwardedPort {
+                            id, namespace: namespace.clone(), service: selected_svc.name.clone(), pod: None, pod_name: None, node_name: None, container_port: None, port, bind_address, local_port: port, status, started_at: Instant::now(), last_reconnect: None, reconnects: 0, traffic, sender
+                        })
+                    }
+                    Ok(None) => {
+                        let _ = message_sender.send(StatusMessage::warning(no_pod_text.clone()));
+                        None
+                    }
+                    Err(PortnordError::Forward(text)) => {
+                        let _ = message_sender.send(StatusMessage::error(text));
+                        None
+                    }
+                    Err(error) => {
+                        if let PortnordError::Kube(error) = &error {
+                            if is_unauthorized(error) {
+                                let _ = auth_failure_sender.send(());
+                            }
+                        }
+                        let _ = message_sender.send(StatusMessage::error(format!("An error occurred forwarding port {} for service {}: {}", port, selected_svc, error)));
+                        None
+                    }
+                };
+                let _ = bulk_forward_sender.send(forwarded_port);
+            }).map(Box::pin).collect();
+
+            futures::future::join_all(forwarded_ports_futs).await;
+        });
+    }
+
+    /// Adds the forwards started by `forward_all_ports` as they are set up, counting those that failed too.
+    pub fn update_bulk_forwards(&mut self) {
+        while let Ok(forwarded_port) = self.bulk_forward_receiver.try_recv() {
+            self.dirty = true;
+            // Sorting by active forwards may move the selected service around
+            let selected_service = self.service();
+            let id = forwarded_port.as_ref().map(|fw_port| fw_port.id);
+            if let Some(fw_port) = &forwarded_port {
+                self.record_history(HistoryEntry { namespace: fw_port.namespace.clone(), service: fw_port.service.clone(), remote_port: fw_port.port, local_port: fw_port.local_port });
+            }
+            self.forwarded_ports.extend(forwarded_port);
+            let early_updates = id
+                .and_then(|id| self.bulk_forward.as_mut()?.early_updates.remove(&id).map(|changes| (id, changes)));
+            if let Some((id, changes)) = early_updates {
+                for change in changes {
+                    // Not opened in the browser, which only forwards a single port
+                    self.apply_forward_update(id, change);
+                }
+            }
+            self.reconcile_selection(selected_service);
+            let done = match &mut self.bulk_forward {
+                Some(bulk_forward) => {
+                    bulk_forward.done += 1;
+                    bulk_forward.done == bulk_forward.total
+                }
+                None => false,
+            };
+            if done {
+                self.bulk_forward = None;
+            }
+        }
+    }
+
+    /// Checks that the local port can be bound, so that a collision or a privileged port is reported before the forward is added.
+    fn probe_local_port(options: &ForwardOptions, local_port: u16) -> Result<(), String> {
+        if options.dry_run {
+            return Ok(());
+        }
+        let addr = SocketAddr::new(options.bind_address, local_port);
+        match std::net::TcpListener::bind(addr) {
+            Ok(_) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::AddrInUse => Err(format!("Local port {} already in use", local_port)),
+            // Binding ports below 1024 takes root or CAP_NET_BIND_SERVICE on most systems, 80 being forwarded to 8080 then
+            Err(error) if error.kind() == std::io::ErrorKind::PermissionDenied && local_port < 1024 => Err(format!(
+                "Local port {} is privileged and can't be bound without the permission to, forward to a higher local port with p, e.g. {}", local_port, local_port + 8000
+            )),
+            Err(error) => Err(format!("Unable to bind {}: {}", addr, error)),
+        }
+    }
+
+    /// Fails when the namespace doesn't exist; a check that isn't allowed is not treated as an error.
+    async fn check_namespace(client: &dyn ClusterClient, namespace: &str) -> Result<(), Box<dyn std::error::Error>> {
+        match client.get_namespace(namespace).await {
+            Err(kube::Error::Api(response)) if response.code == 404 => Err(format!("Namespace '{}' does not exist", namespace).into()),
+            Err(kube::Error::Api(response)) if response.code == 403 => Ok(()),
+            Err(error) => Err(format!("Unable to check namespace '{}': {}", namespace, error).into()),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    /// Loads the kube config from the given file and for the given context, or infers it like `kubectl` would.
+    async fn load_config(kubeconfig_opt: Option<PathBuf>, context_opt: Option<String>) -> Result<Config, Box<dyn std::error::Error>> {
+        if let Some(path) = kubeconfig_opt {
+            let kubeconfig = Kubeconfig::read_from(&path)
+                .map_err(|error| format!("Unable to read kube config {}: {}", path.display(), error))?;
+            let options = KubeConfigOptions { context: context_opt.clone(), ..KubeConfigOptions::default() };
+            Config::from_custom_kubeconfig(kubeconfig, &options)
+                .await
+                .map_err(|error| match context_opt {
+                    Some(context) => format!("Unable to load kube context '{}' from {}: {}", context, path.display(), error).into(),
+                    None => format!("Unable to load kube config {}: {}", path.display(), error).into(),
+                })
+        } else if let Some(context) = context_opt {
+            let options = KubeConfigOptions { context: Some(context.clone()), ..KubeConfigOptions::default() };
+            Config::from_kubeconfig(&options)
+                .await
+                .map_err(|error| format!("Unable to load kube context '{}': {}", context, error).into())
+        } else {
+            Ok(Config::infer().await?)
+        }
+    }
+
+    async fn reload_client(kubeconfig_opt: Option<PathBuf>, context_opt: Option<String>) -> Option<Arc<dyn ClusterClient>> {
+        let config = AppState::load_config(kubeconfig_opt, context_opt).await.ok()?;
+        let default_namespace = config.default_namespace.clone();
+        Some(Arc::new(KubeClient::new(Client::try_from(config).ok()?, default_namespace)))
+    }
+
+    /// Lists the services of each namespace, only those matching the label selector `labels` when given.
+    async fn get_services(client: &dyn ClusterClient, namespaces: &[String], all_namespaces: bool, labels: Option<&str>) -> Result<ServiceListing, kube::Error> {
+        let listed_namespaces = AppState::listed_namespaces(client, namespaces, all_namespaces);
+        let mut services: Vec<Service> = vec![];
+        for namespace in &listed_namespaces {
+            services.extend(client.list_services(namespace.as_deref(), labels).await?);
+        }
+        // Listing endpoints may not be allowed, in which case the ready counts are just not shown; they carry the labels of their service
+        let mut endpoints = Some(vec![]);
+        for namespace in &listed_namespaces {
+            match (client.list_endpoints(namespace.as_deref(), labels).await, &mut endpoints) {
+                (Ok(list), Some(endpoints)) => endpoints.extend(list),
+                _ => endpoints = None,
+            }
+        }
+        Ok(ServiceListing { services, endpoints })
+    }
+
+    /// Namespaces to list from, a single `None` when listing all of them, the default one when none was given.
+    fn listed_namespaces(client: &dyn ClusterClient, namespaces: &[String], all_namespaces: bool) -> Vec<Option<String>> {
+        if all_namespaces {
+            vec![None]
+        } else if namespaces.is_empty() {
+            vec![Some(client.default_namespace().to_owned())]
+        } else {
+            namespaces.iter().cloned().map(Some).collect()
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn run_port_forward(
+        client: &Arc<dyn ClusterClient>,
+        namespace: &str,
+        service: &str,
+        pods: &PodSelector,
+        port: &RemotePort,
+        local_port: u16,
+        options: &ForwardOptions,
+        reporter: ForwardReporter
+    ) -> Result<Option<Sender<()>>, PortnordError> {
+        if options.dry_run {
+            return Ok(Some(AppState::run_dry_port_forward(reporter)));
+        }
+        match options.proxy_mode {
+            ProxyMode::Tcp => AppState::run_tcp_port_forward(client, namespace, service, pods, port, local_port, options, reporter).await,
+            ProxyMode::Http => AppState::run_http_port_forward(client, namespace, service, pods, port, local_port, options, reporter).await,
+        }
+    }
+
+    /// Reports the forward active right away, its task only waiting to be stopped.
+    fn run_dry_port_forward(reporter: ForwardReporter) -> Sender<()> {
+        let (sender, mut rx) = tokio::sync::mpsc::channel(1);
+        tokio::spawn(async move {
+            let _exit = ServerExit { reporter: reporter.clone(), stopped: CancellationToken::new() };
+            reporter.report(ForwardStatus::Active);
+            rx.recv().await;
+        });
+        sender
+    }
+
+    /// Relays the bytes of each local connection through a port forward of its own.
+    #[allow(clippy::too_many_arguments)]
+    async fn run_tcp_port_forward(
+        client: &Arc<dyn ClusterClient>,
+        namespace: &str,
+        service: &str,
+        pods: &PodSelector,
+        port: &RemotePort,
+        local_port: u16,
+        options: &ForwardOptions,
+        reporter: ForwardReporter
+    ) -> Result<Option<Sender<()>>, PortnordError> {
+        // Checked up front, so that a service without pods is reported like with the HTTP proxy
+        let target_opt = tokio::time::timeout(options.connect_timeout, AppState::resolve_pod(client.as_ref(), namespace, pods, port))
+            .await
+            .map_err(|_| PortnordError::Forward(connect_timed_out(service, port, options.connect_timeout)))??;
+        match target_opt {
+            Some(PodTarget { namespace, name, node, ready, .. }) => {
+                if !ready {
+                    reporter.warn(format!("No ready pod found for service {}, forwarding to {}/{} anyway", service, namespace, name));
+                }
+                reporter.report_pod(&name, node.as_deref());
+            }
+            None => return Ok(None),
+        }
+
+        let (sender, mut rx) = tokio::sync::mpsc::channel(1);
+        let addr = SocketAddr::new(options.bind_address, local_port);
+        let stopped = CancellationToken::new();
+        let (client, namespace, service, pods, port) = (client.clone(), namespace.to_owned(), service.to_owned(), pods.clone(), port.clone());
+        let (connect_timeout, shutdown_timeout) = (options.connect_timeout, options.shutdown_timeout);
+        tokio::spawn(async move {
+            let _exit = ServerExit { reporter: reporter.clone(), stopped: stopped.clone() };
+            let listener = match TcpListener::bind(addr).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    reporter.report(ForwardStatus::Failed(format!("unable to bind {}: {}", addr, e)));
+                    return;
+                }
+            };
+            // The probe connection is skipped rather than relayed to the pod
+            let mut probe_addr = match check_local_port(addr).await {
+                Ok(probe_addr) => Some(probe_addr),
+                Err(reason) => {
+                    reporter.report(ForwardStatus::Failed(reason));
+                    return;
+                }
+            };
+            reporter.report(ForwardStatus::Active);
+            let mut relays = JoinSet::new();
+            loop {
+                let mut local_stream = tokio::select! {
+                    _ = rx.recv() => break,
+                    // Reaps the relays done with, only those in flight being kept
+                    Some(_) = relays.join_next(), if !relays.is_empty() => continue,
+                    accepted = listener.accept() => match accepted {
+                        Ok((_, peer_addr)) if Some(peer_addr) == probe_addr => {
+                            probe_addr = None;
+                            continue;
+                        }
+                        Ok((local_stream, _)) => local_stream,
+                        Err(e) => {
+                            reporter.report(ForwardStatus::Failed(format!("listener on {} failed: {}", addr, e)));
+                            break;
+                        }
+                    },
+                };
+                let (client, namespace, service, pods, port) = (client.clone(), namespace.clone(), service.clone(), pods.clone(), port.clone());
+                let (reporter, stopped) = (reporter.clone(), stopped.clone());
+                relays.spawn(async move {
+                    tokio::select! {
+                        _ = stopped.cancelled() => (),
+                        result = AppState::relay(client.as_ref(), &namespace, &service, &pods, &port, connect_timeout, &mut local_stream, &reporter) => {
+                            if let Err(reason) = result {
+                                reporter.warn(format!("Connection to port {} of service {} failed: {}", port, service, reason));
+                            }
+                        }
+                    }
+                });
+            }
+            // Like the HTTP proxy, the connections in flight are given until the shutdown timeout to complete before being cut,
+            // the receiver being kept open meanwhile, see `AppState::shutdown`
+            drop(listener);
+            let drained = async { while relays.join_next().await.is_some() {} };
+            if tokio::time::timeout(shutdown_timeout, drained).await.is_err() {
+                tracing::warn!("Cut {} connections still open on {} after {}s", relays.len(), addr, shutdown_timeout.as_secs());
+            }
+        });
+
+        Ok(Some(sender))
+    }
+
+    /// Pipes a local connection to the pod until either side closes it, then tallies the bytes relayed.
+    #[allow(clippy::too_many_arguments)]
+    async fn relay(
+        client: &dyn ClusterClient,
+        namespace: &str,
+        service: &str,
+        pods: &PodSelector,
+        port: &RemotePort,
+        connect_timeout: Duration,
+        local_stream: &mut TcpStream,
+        reporter: &ForwardReporter
+    ) -> Result<(), String> {
+        // The port forward is only created once connected, so giving up on the setup leaves nothing behind
+        let setup = async {
+            let PodTarget { namespace: pod_namespace, name: pod_name, node, port: pod_port, .. } = AppState::resolve_pod(client, namespace, pods, port)
+                .await
+                .map_err(|e| { reporter.check_auth(&e); e.to_string() })?
+                .ok_or_else(|| format!("no pod found for service {}", service))?;
+            reporter.report_pod(&pod_name, node.as_deref());
+            let port_forwarder = client.port_forward(&pod_namespace, &pod_name, pod_port).await.map_err(|e| { reporter.check_auth(&e); e.to_string() })?;
+            Ok::<_, String>((pod_namespace, pod_name, pod_port, port_forwarder))
+        };
+        let (pod_namespace, pod_name, pod_port, mut port_forwarder) = tokio::time::timeout(connect_timeout, setup)
+            .await
+            .map_err(|_| connect_timed_out(service, port, connect_timeout))??;
+        let result = match port_forwarder.take_stream(pod_port) {
+            Some(mut remote_stream) => tokio::io::copy_bidirectional(local_stream, &mut remote_stream)
+                .await
+                .map_err(|e| format!("relaying to pod {}/{} failed: {}", pod_namespace, pod_name, e)),
+            None => Err(format!("pod {}/{} did not open port {}", pod_namespace, pod_name, pod_port)),
+        };
+        port_forwarder.abort();
+        let (sent, received) = result?;
+        reporter.traffic.sent.fetch_add(sent, Ordering::Relaxed);
+        reporter.traffic.received.fetch_add(received, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Proxies HTTP requests over a single port forward, counting the bytes of the bodies as they stream.
+    #[allow(clippy::too_many_arguments)]
+    async fn run_http_port_forward(
+        client: &Arc<dyn ClusterClient>,
+        namespace: &str,
+        service: &str,
+        pods: &PodSelector,
+        port: &RemotePort,
+        local_port: u16,
+        options: &ForwardOptions,
+        reporter: ForwardReporter
+    ) -> Result<Option<Sender<()>>, PortnordError> {
+        let (sender, connection_ended) = match AppState::connect(client.as_ref(), namespace, service, pods, port, options.connect_timeout, &reporter).await? {
+            Some(connection) => connection,
+            None => return Ok(None),
+        };
+
+        let handle_request = |
+            context: Arc<Mutex<hyper::client::conn::SendRequest<hyper::Body>>>,
+            traffic: Arc<Traffic>,
+            req: Request<Body>| async move {
+            let sent = traffic.clone();
+            let req = req.map(|body| count_body(body, move |len| sent.sent.fetch_add(len, Ordering::Relaxed)));
+            let sender = context.lock();
+            let response = sender.await.send_request(req).await?;
+            let response = response.map(|body| count_body(body, move |len| traffic.received.fetch_add(len, Ordering::Relaxed)));
+            Ok(response) as Result<Response<Body>, hyper::Error>
+        };
+        let context = Arc::new(Mutex::new(sender));
+        let server_context = context.clone();
+        let traffic = reporter.traffic.clone();
+        let make_service = make_service_fn(move |_conn| {
+            let context = server_context.clone();
+            let traffic = traffic.clone();
+            let service = service_fn(move |req| handle_request(context.clone(), traffic.clone(), req));
+            async move { Ok::<_, hyper::Error>(service) }
+        });
+
+        let (sender, mut rx) = tokio::sync::mpsc::channel(1);
+        let addr = SocketAddr::new(options.bind_address, local_port);
+        let stopped = CancellationToken::new();
+
+        let server_reporter = reporter.clone();
+        let server_stopped = stopped.clone();
+        tokio::spawn(async move {
+            let _exit = ServerExit { reporter: server_reporter.clone(), stopped: server_stopped };
+            let builder = match Server::try_bind(&addr) {
+                Ok(builder) => builder,
+                Err(e) => {
+                    server_reporter.report(ForwardStatus::Failed(format!("unable to bind {}: {}", addr, e)));
+                    return;
+                }
+            };
+            if let Err(reason) = check_local_port(addr).await {
+                server_reporter.report(ForwardStatus::Failed(reason));
+                return;
+            }
+            let server = builder
+            .serve(make_service)
+            // Borrowing the receiver keeps it open until the requests in flight completed, see `AppState::shutdown`
+            .with_graceful_shutdown(async {
+                rx.recv().await;
+            });
+
+            server_reporter.report(ForwardStatus::Active);
+            if let Err(e) = server.await {
+                server_reporter.report(ForwardStatus::Failed(format!("server on {} failed: {}", addr, e)));
+            }
+        });
+
+        tokio::spawn(AppState::supervise_connection(
+            client.clone(), namespace.to_owned(), service.to_owned(), pods.clone(), port.clone(), connection_ended, context, options.max_retries, options.connect_timeout, reporter, stopped
+        ));
+
+        Ok(Some(sender))
+    }
+
+    /// Finds the pod targeted directly, or a pod matching the selector of the service in `namespace`, along with its port `port`.
+    async fn resolve_pod(client: &dyn ClusterClient, namespace: &str, pods: &PodSelector, port: &RemotePort) -> Result<Option<PodTarget>, kube::Error> {
+        let selector = match pods {
+            PodSelector::Labels(Some(selector)) => selector,
+            // Its endpoints, if any, are managed by hand rather than picked among pods
+            PodSelector::Labels(None) => return Ok(None),
+            // Pods are identified by namespace and name, an explicit pod being in the namespace of the service
+            PodSelector::Name(pod) => {
+                // Only fetched for its node and named ports, the port forward telling whether the pod exists
+                let fetched = client.get_pod(namespace, pod).await.ok();
+                let node = fetched.as_ref().and_then(node_name);
+                let pod_port = match port {
+                    RemotePort::Number(number) => Some(*number),
+                    RemotePort::Named(_) => fetched.as_ref().and_then(|fetched| port.on(fetched)),
+                };
+                return Ok(pod_port.map(|pod_port| PodTarget { namespace: namespace.to_owned(), name: pod.to_owned(), node, port: pod_port, ready: true }));
+            }
+        };
+        let pods = client.list_pods(namespace, Some(selector)).await?;
+        Ok(pod_target(&pods, namespace, port))
+    }
+
+    /// Opens a port forward to `pod` when given, or to a pod backing `service` in `namespace` otherwise,
+    /// returning the HTTP sender to use and a future resolving with the reason once the connection is no longer usable.
+    async fn connect(
+        client: &dyn ClusterClient,
+        namespace: &str,
+        service: &str,
+        pods: &PodSelector,
+        port: &RemotePort,
+        connect_timeout: Duration,
+        reporter: &ForwardReporter
+    ) -> Result<Option<(SendRequest<Body>, ConnectionEnded)>, PortnordError> {
+        // Every step shares the same deadline, the port forward being aborted if the handshake doesn't make it
+        let deadline = tokio::time::Instant::now() + connect_timeout;
+        let timed_out = |_| PortnordError::Forward(connect_timed_out(service, port, connect_timeout));
+        let target_opt = tokio::time::timeout_at(deadline, AppState::resolve_pod(client, namespace, pods, port)).await.map_err(timed_out)??;
+        if let Some(PodTarget { namespace: pod_namespace, name: pod_name, node, port: pod_port, ready }) = target_opt {
+            if !ready {
+                reporter.warn(format!("No ready pod found for service {}, forwarding to {}/{} anyway", service, pod_namespace, pod_name));
+            }
+            reporter.report_pod(&pod_name, node.as_deref());
+            let mut port_forwarder = tokio::time::timeout_at(deadline, client.port_forward(&pod_namespace, &pod_name, pod_port)).await.map_err(timed_out)??;
+            let Some(stream) = port_forwarder.take_stream(pod_port) else {
+                port_forwarder.abort();
+                return Err(PortnordError::Forward(format!("pod {}/{} did not open port {}", pod_namespace, pod_name, pod_port)));
+            };
+            let port_error = port_forwarder.take_error(pod_port);
+            let handshake = match tokio::time::timeout_at(deadline, hyper::client::conn::handshake(stream)).await {
+                Ok(handshake) => handshake.map_err(|e| PortnordError::Kube(kube::Error::HyperError(e))),
+                Err(elapsed) => Err(timed_out(elapsed)),
+            };
+            let (sender, connection) = match handshake {
+                Ok(handshake) => handshake,
+                Err(error) => {
+                    port_forwarder.abort();
+                    return Err(error);
+                }
+            };
+            let connection_ended = async move {
+                let reason = tokio::select! {
+                    result = connection => match result {
+                        Ok(()) => "connection closed".to_string(),
+                        Err(e) => format!("connection failed: {}", e),
+                    },
+                    error = async {
+                        match port_error {
+                            Some(port_error) => port_error.await,
+                            None => futures::future::pending().await,
+                        }
+                    } => match error {
+                        Some(error) => format!("port forwarding on pod {}/{} failed: {}", pod_namespace, pod_name, error),
+                        None => format!("port forwarding on pod {}/{} ended", pod_namespace, pod_name),
+                    },
+                };
+                port_forwarder.abort();
+                reason
+            };
+            return Ok(Some((sender, Box::pin(connection_ended))));
+        }
+
+        Ok(None)
+    }
+
+    /// Re-establishes the connection of a forward whenever it ends (e.g. because the pod
+    /// restarted), backing off between attempts, until the forward is stopped.
+    #[allow(clippy::too_many_arguments)]
+    async fn supervise_connection(
+        client: Arc<dyn ClusterClient>,
+        namespace: String,
+        service: String,
+        pods: PodSelector,
+        port: RemotePort,
+        mut connection_ended: ConnectionEnded,
+        context: Arc<Mutex<SendRequest<Body>>>,
+        max_retries: u32,
+        connect_timeout: Duration,
+        reporter: ForwardReporter,
+        stopped: CancellationToken
+    ) {
+        loop {
+            let mut reason = tokio::select! {
+                _ = stopped.cancelled() => return,
+                reason = &mut connection_ended => reason,
+            };
+            let mut attempt = 0;
+            loop {
+                if attempt == max_retries {
+                    reporter.report(ForwardStatus::Failed(format!("{}, gave up after {} retries", reason, max_retries)));
+                    return;
+                }
+                attempt += 1;
+                tracing::warn!("Reconnecting port {} for service {} ({}/{}): {}", port, service, attempt, max_retries, reason);
+                reporter.report(ForwardStatus::Connecting);
+                let backoff = (RECONNECT_BACKOFF * 2u32.pow(attempt - 1)).min(MAX_RECONNECT_BACKOFF);
+                tokio::select! {
+                    _ = stopped.cancelled() => return,
+                    _ = tokio::time::sleep(backoff) => (),
+                }
+                match AppState::connect(client.as_ref(), &namespace, &service, &pods, &port, connect_timeout, &reporter).await {
+                    Ok(Some((sender, ended))) => {
+                        *context.lock().await = sender;
+                        connection_ended = ended;
+                        tracing::info!("Reconnected port {} for service {}", port, service);
+                        reporter.report(ForwardStatus::Active);
+                        break;
+                    }
+                    Ok(None) => reason = format!("no pod found for service {}", service),
+                    Err(e) => {
+                        if let PortnordError::Kube(error) = &e {
+                            reporter.check_auth(error);
+                        }
+                        reason = e.to_string();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether the cluster rejected the credentials, e.g. because the token expired.
+fn is_unauthorized(error: &kube::Error) -> bool {
+    match error {
+        kube::Error::Api(response) => response.code == 401,
+        kube::Error::Auth(_) => true,
+        _ => false,
+    }
+}
+
+/// Parses `START-END`, both ends included, or a single port.
+fn parse_port_range(input: &str) -> Option<RangeInclusive<u16>> {
+    let (start, end) = input.split_once('-').unwrap_or((input, input));
+    match (start.parse::<u16>(), end.parse::<u16>()) {
+        (Ok(start @ 1..), Ok(end)) if start <= end => Some(start..=end),
+        _ => None,
+    }
+}
+
+/// Connects to the local port of a new forward, to make sure it accepts connections;
+/// returns the local address of the probe connection, which is closed right away.
+async fn check_local_port(addr: SocketAddr) -> Result<SocketAddr, String> {
+    // Listening on all interfaces, the port is reachable on the loopback one
+    let target = match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => SocketAddr::new(Ipv4Addr::LOCALHOST.into(), addr.port()),
+        IpAddr::V6(ip) if ip.is_unspecified() => SocketAddr::new(Ipv6Addr::LOCALHOST.into(), addr.port()),
+        _ => addr,
+    };
+    match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, TcpStream::connect(target)).await {
+        Ok(Ok(stream)) => stream.local_addr().map_err(|e| format!("unable to check {}: {}", addr, e)),
+        Ok(Err(e)) => Err(format!("{} doesn't accept connections: {}", addr, e)),
+        Err(_) => Err(format!("{} didn't accept a connection within {}s", addr, HEALTH_CHECK_TIMEOUT.as_secs())),
+    }
+}
+
+/// Explains why a forward was abandoned when connecting to its pod took too long.
+fn connect_timed_out(service: &str, port: &RemotePort, connect_timeout: Duration) -> String {
+    format!("Timed out connecting port {} of service {} after {}s", port, service, connect_timeout.as_secs())
+}
+
+/// Pod of `pods` to connect to in `namespace`, with its port `port`.
+fn pod_target(pods: &[Pod], namespace: &str, port: &RemotePort) -> Option<PodTarget> {
+    let pods: Vec<Pod> = pods
+        .iter()
+        .filter(|pod| pod.namespace().as_deref() == Some(namespace))
+        // Like the endpoints of the service, which leave out the pods without a container port of that name
+        .filter(|pod| port.on(pod).is_some())
+        .cloned()
+        .collect();
+    pick_pod(&pods).and_then(|pod| Some(PodTarget {
+        namespace: namespace.to_owned(),
+        name: pod.name(),
+        node: node_name(pod),
+        port: port.on(pod)?,
+        ready: is_pod_ready(pod),
+    }))
+}
+
+/// Picks a running and ready pod, falling back to the first pod not being deleted, then to any pod.
+fn pick_pod(pods: &[Pod]) -> Option<&Pod> {
+    pods.iter()
+        .find(|pod| is_pod_ready(pod))
+        .or_else(|| pods.iter().find(|pod| pod.metadata.deletion_timestamp.is_none()))
+        .or_else(|| pods.first())
+}
+
+/// Node the pod was scheduled on, if any yet.
+fn node_name(pod: &Pod) -> Option<String> {
+    pod.spec.as_ref().and_then(|spec| spec.node_name.clone())
+}
+
+/// Whether the pod is running, not terminating, and has its `Ready` condition set.
+fn is_pod_ready(pod: &Pod) -> bool {
+    let status = match &pod.status {
+        Some(status) => status,
+        None => return false,
+    };
+    pod.metadata.deletion_timestamp.is_none()
+        && status.phase.as_deref() == Some("Running")
+        && status.conditions
+            .iter()
+            .flatten()
+            .any(|condition| condition.type_ == "Ready" && condition.status == "True")
+}
+
+/// Wraps `body` so that the size of each chunk is passed to `count` as it streams through.
+fn count_body(body: Body, count: impl Fn(u64) -> u64 + Send + Sync + 'static) -> Body {
+    // Empty bodies are left alone, wrapping them would make hyper send them chunked
+    if body.is_end_stream() {
+        body
+    } else {
+        Body::wrap_stream(body.inspect_ok(move |chunk| { count(chunk.len() as u64); }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::{
+        api::core::v1::{ServicePort, ServiceSpec},
+        apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time},
+        chrono::Utc,
+    };
+
+    use crate::cluster::fake::{self, FakeCluster};
+
+    use super::*;
+
+    /// Forwards are only recorded, neither the pods nor the local ports being reached.
+    fn dry_run_options() -> ForwardOptions {
+        ForwardOptions {
+            max_retries: 0,
+            bind_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            proxy_mode: ProxyMode::Tcp,
+            confirm_forward_all: false,
+            dry_run: true,
+            connect_timeout: Duration::from_secs(1),
+            shutdown_timeout: Duration::from_secs(1),
+        }
+    }
+
+    /// State of an empty cluster, the services being applied by hand.
+    async fn offline_state() -> AppState {
+        AppState::with_client(Arc::new(FakeCluster::default()), vec![], false, None, None, dry_run_options(), false, vec![]).await.unwrap()
+    }
+
+    /// State of the services of `cluster` in `namespaces`, once listed.
+    async fn listed(cluster: FakeCluster, namespaces: &[&str], all_namespaces: bool) -> AppState {
+        let namespaces = namespaces.iter().map(|namespace| namespace.to_string()).collect();
+        let mut state = AppState::with_client(Arc::new(cluster), namespaces, all_namespaces, None, None, dry_run_options(), false, vec![])
+            .await
+            .unwrap();
+        state.refresh_services();
+        while state.loading {
+            tokio::task::yield_now().await;
+            state.update_services().await;
+        }
+        state
+    }
+
+    fn cluster(services: Vec<Service>) -> FakeCluster {
+        FakeCluster { services, ..FakeCluster::default() }
+    }
+
+    fn service(name: Option<&str>, spec: Option<ServiceSpec>) -> Service {
+        Service { metadata: ObjectMeta { name: name.map(str::to_owned), ..ObjectMeta::default() }, spec, ..Service::default() }
+    }
+
+    fn spec_with_ports(ports: Option<&[i32]>) -> Option<ServiceSpec> {
+        let ports = ports.map(|ports| ports.iter().map(|&port| ServicePort { port, ..ServicePort::default() }).collect());
+        Some(ServiceSpec { ports, ..ServiceSpec::default() })
+    }
+
+    fn port_info(port: i32, target_port: Option<IntOrString>) -> PortInfo {
+        PortInfo { port, name: None, target_port, protocol: "TCP".to_string() }
+    }
+
+    fn key(name: &str) -> ServiceKey {
+        ServiceKey { namespace: "default".to_string(), name: name.to_owned() }
+    }
+
+    fn names(state: &AppState) -> Vec<String> {
+        state.service_list().iter().map(|service| state.service_label(service)).collect()
+    }
+
+    fn last_message(state: &AppState) -> &str {
+        state.messages.last().map(|message| message.text.as_str()).unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn unknown_namespace_is_refused() {
+        let cluster = cluster(vec![fake::service("default", "web", vec![])]);
+        let result = AppState::with_client(Arc::new(cluster), vec!["missing".to_string()], false, None, None, dry_run_options(), false, vec![]).await;
+        assert_eq!(result.err().map(|error| error.to_string()), Some("Namespace 'missing' does not exist".to_string()));
+    }
+
+    #[tokio::test]
+    async fn lists_the_services_of_the_namespace_only() {
+        let state = listed(cluster(vec![
+            fake::service("default", "web", vec![fake::service_port(80, None)]),
+            fake::service("default", "api", vec![fake::service_port(8080, None)]),
+            fake::service("other", "db", vec![fake::service_port(5432, None)]),
+        ]), &[], false).await;
+        assert_eq!(names(&state), ["api", "web"]);
+    }
+
+    #[tokio::test]
+    async fn services_without_a_spec_or_ports_are_listed_without_ports() {
+        let mut state = offline_state().await;
+        state.apply_services(vec![
+            service(Some("external"), None),
+            service(Some("headless"), spec_with_ports(None)),
+            service(Some("empty"), spec_with_ports(Some(&[]))),
+            service(Some("web"), spec_with_ports(Some(&[80, 443]))),
+        ]);
+        assert_eq!(names(&state), ["empty", "external", "headless", "web"]);
+        for (service, ports) in [("external", vec![]), ("headless", vec![]), ("empty", vec![]), ("web", vec![80, 443])] {
+            assert_eq!(state.ports_by_service[&key(service)].iter().map(|port| port.port).collect::<Vec<i32>>(), ports);
+        }
+        assert!(state.messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn services_without_a_name_are_skipped() {
+        let mut state = offline_state().await;
+        state.apply_services(vec![service(None, None), service(Some("web"), spec_with_ports(Some(&[80])))]);
+        assert_eq!(names(&state), ["web"]);
+        assert_eq!(state.messages.iter().map(|message| message.text.as_str()).collect::<Vec<_>>(), ["Skipped 1 services without a name"]);
+    }
+
+    #[tokio::test]
+    async fn navigating_an_empty_list_selects_nothing() {
+        let mut state = offline_state().await;
+        state.next();
+        state.previous();
+        state.select();
+        assert_eq!(state.service_selection.selected(), None);
+        assert_eq!(state.port_selection.selected(), None);
+    }
+
+    #[tokio::test]
+    async fn navigating_a_single_element_list_stays_on_it() {
+        let mut state = offline_state().await;
+        state.apply_services(vec![service(Some("web"), spec_with_ports(Some(&[80])))]);
+        state.next();
+        state.next();
+        state.previous();
+        assert_eq!(state.service_selection.selected(), Some(0));
+        state.select();
+        state.next();
+        state.previous();
+        assert_eq!(state.port_selection.selected(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn selection_wraps_around_the_services() {
+        let mut state = listed(cluster(vec![
+            fake::service("default", "a", vec![]),
+            fake::service("default", "b", vec![]),
+            fake::service("default", "c", vec![]),
+        ]), &[], false).await;
+        state.select_service(0);
+        state.previous();
+        assert_eq!(state.service(), Some(key("c")));
+        state.next();
+        state.next();
+        assert_eq!(state.service(), Some(key("b")));
+        state.select_last();
+        assert_eq!(state.service(), Some(key("c")));
+    }
+
+    #[test]
+    fn pick_pod_prefers_ready_then_live_pods() {
+        assert!(pick_pod(&[]).is_none());
+        let not_ready = fake::pod("default", "web-1", "web", false, &[]);
+        let ready = fake::pod("default", "web-2", "web", true, &[]);
+        let mut deleted = fake::pod("default", "web-3", "web", true, &[]);
+        deleted.metadata.deletion_timestamp = Some(Time(Utc::now()));
+        let pick = |pods: &[Pod]| pick_pod(pods).map(ResourceExt::name);
+        assert_eq!(pick(&[not_ready.clone(), ready.clone()]), Some("web-2".to_string()));
+        assert_eq!(pick(&[deleted.clone(), not_ready.clone()]), Some("web-1".to_string()));
+        assert_eq!(pick(&[deleted.clone()]), Some("web-3".to_string()));
+        assert_eq!(pick(&[ready]), Some("web-2".to_string()));
+    }
+
+    #[test]
+    fn pod_target_skips_pods_of_other_namespaces_and_without_the_port() {
+        let pods = [
+            fake::pod("other", "web-1", "web", true, &[("http", 8080)]),
+            fake::pod("default", "web-2", "web", true, &[("grpc", 9000)]),
+            fake::pod("default", "web-3", "web", false, &[("http", 8081)]),
+        ];
+        let target = pod_target(&pods, "default", &RemotePort::Named("http".to_string())).unwrap();
+        assert_eq!((target.name.as_str(), target.port, target.ready), ("web-3", 8081, false));
+        assert!(pod_target(&pods, "missing", &RemotePort::Number(80)).is_none());
+    }
+
+    #[test]
+    fn port_range_is_parsed_inclusively() {
+        assert_eq!(parse_port_range("8000-8010"), Some(8000..=8010));
+        assert_eq!(parse_port_range("8080"), Some(8080..=8080));
+        assert_eq!(parse_port_range("0-10"), None);
+        assert_eq!(parse_port_range("9000-8000"), None);
+        assert_eq!(parse_port_range("80-http"), None);
+        assert_eq!(parse_port_range(""), None);
+    }
+
+    #[tokio::test]
+    async fn selection_follows_the_service_across_listings() {
+        let mut state = offline_state().await;
+        state.apply_services(vec![
+            service(Some("a"), spec_with_ports(Some(&[80]))),
+            service(Some("b"), spec_with_ports(Some(&[80, 443]))),
+        ]);
+        state.select_service(1);
+        state.select();
+        state.next();
+        state.apply_services(vec![
+            service(Some("a"), spec_with_ports(Some(&[80]))),
+            service(Some("aa"), None),
+            service(Some("b"), spec_with_ports(Some(&[80, 443]))),
+        ]);
+        assert_eq!(state.service_selection.selected(), Some(2));
+        assert_eq!(state.port_selection.selected(), Some(1));
+        state.cycle_sort_mode();
+        assert_eq!(names(&state), ["b", "aa", "a"]);
+        assert_eq!(state.service(), Some(key("b")));
+
+        // Once gone, the selection moves on rather than landing on another service
+        state.apply_services(vec![service(Some("a"), spec_with_ports(Some(&[80])))]);
+        assert_eq!(state.service(), Some(key("a")));
+        assert_eq!(state.port_selection.selected(), None);
+    }
+
+    #[tokio::test]
+    async fn arrows_move_within_the_focused_pane() {
+        let mut state = listed(cluster(vec![
+            fake::service("default", "a", vec![fake::service_port(80, None), fake::service_port(443, None)]),
+            fake::service("default", "b", vec![fake::service_port(8080, None)]),
+        ]), &[], false).await;
+        state.select_service(0);
+        state.switch_focus();
+        assert!(state.focused_pane() == Pane::Ports);
+        state.next();
+        assert_eq!(state.port_selection.selected(), Some(1));
+        assert_eq!(state.service().map(|service| service.name), Some("a".to_string()));
+
+        // Back on the services, the port stays selected until another service is
+        state.switch_focus();
+        assert!(state.focused_pane() == Pane::Services);
+        assert_eq!(state.port_selection.selected(), Some(1));
+        state.next();
+        assert_eq!(state.service().map(|service| service.name), Some("b".to_string()));
+        assert_eq!(state.port_selection.selected(), None);
+
+        // The port last selected in a service is selected again when coming back to it
+        state.previous();
+        state.switch_focus();
+        assert_eq!(state.port_selection.selected(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn toggling_a_port_forwards_then_stops_it() {
+        let mut state = listed(cluster(vec![fake::service("default", "web", vec![fake::service_port(80, None), fake::service_port(443, None)])]), &[], false).await;
+        state.select_service(0);
+        state.select();
+        state.next();
+        state.toggle_port_forwarding().await;
+        let forwarded: Vec<u16> = state.forwarded_ports.iter().map(|fw_port| fw_port.port).collect();
+        assert_eq!(forwarded, [443]);
+        state.toggle_port_forwarding().await;
+        assert!(state.forwarded_ports.is_empty());
+        assert_eq!(last_message(&state), "Stopped forwarding port 443 for service web");
+    }
+
+    #[tokio::test]
+    async fn restarting_a_forward_starts_it_again_once_its_server_exited() {
+        let mut state = listed(cluster(vec![fake::service("default", "web", vec![fake::service_port(80, None)])]), &[], false).await;
+        state.select_service(0);
+        state.select();
+        state.toggle_port_forwarding().await;
+        let id = state.forwarded_ports[0].id;
+        state.restart_selected_forward().await;
+        assert!(state.forwarded_ports.is_empty());
+        assert_eq!(last_message(&state), "Restarting port 80 of service web…");
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while state.forwarded_ports.is_empty() {
+                tokio::task::yield_now().await;
+                state.update_restarts().await;
+            }
+        }).await.unwrap();
+        assert_eq!(state.forwarded_ports[0].port, 80);
+        assert_ne!(state.forwarded_ports[0].id, id);
+    }
+
+    #[tokio::test]
+    async fn toggling_a_service_forwards_all_its_ports() {
+        let mut state = listed(cluster(vec![fake::service("default", "web", vec![fake::service_port(80, None), fake::service_port(443, None)])]), &[], false).await;
+        state.select_service(0);
+        state.toggle_port_forwarding().await;
+        while state.bulk_forward.is_some() {
+            tokio::task::yield_now().await;
+            state.update_bulk_forwards();
+        }
+        let mut forwarded: Vec<u16> = state.forwarded_ports.iter().map(|fw_port| fw_port.port).collect();
+        forwarded.sort();
+        assert_eq!(forwarded, [80, 443]);
+        state.toggle_port_forwarding().await;
+        assert!(state.forwarded_ports.is_empty());
+    }
+
+    #[tokio::test]
+    async fn toggling_a_service_without_ports_warns() {
+        let mut state = listed(cluster(vec![fake::service("default", "external", vec![])]), &[], false).await;
+        state.select_service(0);
+        state.toggle_port_forwarding().await;
+        assert!(state.forwarded_ports.is_empty());
+        assert!(state.bulk_forward.is_none());
+        assert_eq!(last_message(&state), "Service external exposes no ports");
+    }
+
+    #[tokio::test]
+    async fn same_named_services_are_told_apart_by_namespace() {
+        let mut state = listed(cluster(vec![
+            fake::service("blue", "web", vec![fake::service_port(80, None)]),
+            fake::service("green", "web", vec![fake::service_port(80, None)]),
+        ]), &[], true).await;
+        assert_eq!(names(&state), ["blue/web", "green/web"]);
+        state.select_service(1);
+        state.select();
+        state.toggle_port_forwarding().await;
+        let forwarded: Vec<&str> = state.forwarded_ports.iter().map(|fw_port| fw_port.namespace.as_str()).collect();
+        assert_eq!(forwarded, ["green"]);
+        assert!(state.forwarded_ports_for_service(&ServiceKey { namespace: "blue".to_string(), name: "web".to_string() }).is_empty());
+    }
+
+    #[tokio::test]
+    async fn service_filter_keeps_the_selection_once_cleared() {
+        let mut state = listed(cluster(vec![
+            fake::service("default", "api", vec![]),
+            fake::service("default", "web", vec![]),
+            fake::service("default", "webhook", vec![]),
+        ]), &[], false).await;
+        state.start_filter();
+        "WEB".chars().for_each(|c| state.push_filter_char(c));
+        assert_eq!(names(&state), ["web", "webhook"]);
+        state.confirm_filter();
+        state.next();
+        assert_eq!(state.service(), Some(key("webhook")));
+        state.clear_filter();
+        assert_eq!(names(&state), ["api", "web", "webhook"]);
+        assert_eq!(state.service(), Some(key("webhook")));
+    }
+
+    #[tokio::test]
+    async fn empty_service_filter_is_dropped() {
+        let mut state = listed(cluster(vec![fake::service("default", "web", vec![])]), &[], false).await;
+        state.start_filter();
+        state.push_filter_char('x');
+        assert!(state.service_list().is_empty());
+        assert_eq!(state.service(), None);
+        state.pop_filter_char();
+        state.confirm_filter();
+        assert_eq!(state.service_filter, None);
+        assert_eq!(names(&state), ["web"]);
+    }
+
+    #[tokio::test]
+    async fn port_filter_matches_the_number_or_the_name() {
+        let http = ServicePort { name: Some("http".to_string()), ..fake::service_port(8080, None) };
+        let metrics = ServicePort { name: Some("metrics".to_string()), ..fake::service_port(9090, None) };
+        let mut state = listed(cluster(vec![fake::service("default", "web", vec![http, metrics])]), &[], false).await;
+        state.select_service(0);
+        state.select();
+        state.start_filter();
+        assert!(state.filtering == Some(FilterTarget::Ports));
+        "met".chars().for_each(|c| state.push_filter_char(c));
+        let ports: Vec<i32> = state.port_list().iter().map(|port| port.port).collect();
+        assert_eq!(ports, [9090]);
+        state.confirm_filter();
+        state.start_filter();
+        (0..3).for_each(|_| state.pop_filter_char());
+        "80".chars().for_each(|c| state.push_filter_char(c));
+        let ports: Vec<i32> = state.port_list().iter().map(|port| port.port).collect();
+        assert_eq!(ports, [8080]);
+        // Leaving the ports drops their filter
+        state.deselect();
+        assert_eq!(state.port_list().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn type_filter_cycles_through_the_listed_types() {
+        let mut node_port = fake::service("default", "ingress", vec![fake::service_port(80, None)]);
+        node_port.spec.as_mut().unwrap().type_ = Some("NodePort".to_string());
+        let mut state = listed(cluster(vec![fake::service("default", "web", vec![fake::service_port(80, None)]), node_port]), &[], false).await;
+        state.cycle_type_filter();
+        assert_eq!(names(&state), ["web"]);
+        state.cycle_type_filter();
+        assert_eq!(names(&state), ["ingress"]);
+        state.cycle_type_filter();
+        assert_eq!(names(&state), ["ingress", "web"]);
+    }
+
+    #[test]
+    fn remote_port_is_the_target_port_of_the_service_port() {
+        assert!(matches!(RemotePort::of(&port_info(80, Some(IntOrString::Int(8080)))), RemotePort::Number(8080)));
+        assert!(matches!(RemotePort::of(&port_info(80, Some(IntOrString::String("http".to_string())))), RemotePort::Named(name) if name == "http"));
+        assert!(matches!(RemotePort::of(&port_info(80, None)), RemotePort::Number(80)));
+    }
+
+    #[test]
+    fn named_remote_port_is_looked_up_on_the_pod() {
+        let pod = fake::pod("default", "web-1", "web", true, &[("http", 8080), ("metrics", 9090)]);
+        assert_eq!(RemotePort::Named("metrics".to_string()).on(&pod), Some(9090));
+        assert_eq!(RemotePort::Named("grpc".to_string()).on(&pod), None);
+        assert_eq!(RemotePort::Number(5000).on(&pod), Some(5000));
+    }
+
+    #[tokio::test]
+    async fn resolve_pod_prefers_a_ready_pod_of_the_service() {
+        let cluster = FakeCluster {
+            pods: vec![
+                fake::pod("default", "web-1", "web", false, &[("http", 8080)]),
+                fake::pod("default", "web-2", "web", true, &[("http", 8080)]),
+                fake::pod("default", "api-1", "api", true, &[("http", 8080)]),
+            ],
+            ..FakeCluster::default()
+        };
+        let pods = PodSelector::Labels(Some("app=web".to_string()));
+        let target = AppState::resolve_pod(&cluster, "default", &pods, &RemotePort::Named("http".to_string())).await.unwrap().unwrap();
+        assert_eq!((target.name.as_str(), target.port, target.ready), ("web-2", 8080, true));
+        assert!(AppState::resolve_pod(&cluster, "default", &PodSelector::Labels(None), &RemotePort::Number(80)).await.unwrap().is_none());
+    }
+
+    /// State of the services of the default namespace of `cluster`, forwarding for real with the proxy `proxy_mode`.
+    async fn forwarding(cluster: FakeCluster, proxy_mode: ProxyMode) -> AppState {
+        let options = ForwardOptions { proxy_mode, dry_run: false, ..dry_run_options() };
+        let mut state = AppState::with_client(Arc::new(cluster), vec![], false, None, None, options, false, vec![]).await.unwrap();
+        state.refresh_services();
+        while state.loading {
+            tokio::task::yield_now().await;
+            state.update_services().await;
+        }
+        state
+    }
+
+    /// A web service on port 80, whose pod opens port 8080 instead when forwarded to.
+    fn cluster_opening_another_port() -> FakeCluster {
+        FakeCluster {
+            services: vec![fake::service("default", "web", vec![fake::service_port(80, None)])],
+            pods: vec![fake::pod("default", "web-1", "web", true, &[("http", 80)])],
+            opened_port: Some(8080),
+            ..FakeCluster::default()
+        }
+    }
+
+    fn unused_local_port() -> u16 {
+        std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap().port()
+    }
+
+    #[tokio::test]
+    async fn http_forward_fails_when_the_pod_does_not_open_the_port() {
+        let cluster = cluster_opening_another_port();
+        let aborted_forwards = cluster.aborted_forwards.clone();
+        let mut state = forwarding(cluster, ProxyMode::Http).await;
+        state.start_port_forwarding_or_report(&key("web"), None, None, 80, unused_local_port()).await;
+        assert_eq!(last_message(&state), "pod default/web-1 did not open port 80");
+        assert!(state.forwarded_ports.is_empty());
+        assert_eq!(aborted_forwards.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn tcp_connection_is_closed_when_the_pod_does_not_open_the_port() {
+        let cluster = cluster_opening_another_port();
+        let aborted_forwards = cluster.aborted_forwards.clone();
+        let mut state = forwarding(cluster, ProxyMode::Tcp).await;
+        let local_port = unused_local_port();
+        // Targeting the pod, so that the forward is left out of the history saved on disk
+        state.start_port_forwarding_or_report(&key("web"), Some("web-1".to_string()), None, 80, local_port).await;
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while !matches!(state.forwarded_ports[0].status, ForwardStatus::Active) {
+                tokio::task::yield_now().await;
+                state.update_forwards();
+            }
+        }).await.unwrap();
+
+        let mut local_stream = TcpStream::connect((Ipv4Addr::LOCALHOST, local_port)).await.unwrap();
+        let mut received = vec![];
+        tokio::time::timeout(Duration::from_secs(1), tokio::io::AsyncReadExt::read_to_end(&mut local_stream, &mut received)).await.unwrap().unwrap();
+        assert!(received.is_empty());
+        let failure = "Connection to port 80 of service web failed: pod default/web-1 did not open port 80";
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while last_message(&state) != failure {
+                tokio::task::yield_now().await;
+                state.update_messages();
+            }
+        }).await.unwrap();
+        assert_eq!(aborted_forwards.load(Ordering::SeqCst), 1);
+        state.stop_all_forwards().await;
+    }
+}
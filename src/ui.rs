@@ -1,59 +1,376 @@
-use std::{collections::BTreeMap, time::Duration};
+use std::{borrow::Cow, collections::{BTreeMap, VecDeque}, io, thread, time::{Duration, Instant}};
 
-use tui::{Terminal, backend::{CrosstermBackend, Backend}, widgets::{Block, Borders, Paragraph, ListItem, List}, text::{Span, Spans}, style::{Style, Modifier, Color}, layout::{Alignment, Rect, Layout, Direction, Constraint}, Frame};
+use tui::{Terminal, backend::{CrosstermBackend, Backend}, widgets::{Block, Borders, Clear, Paragraph, ListItem, List, ListState}, text::{Span, Spans}, style::{Style, Modifier, Color}, layout::{Alignment, Rect, Layout, Direction, Constraint}, Frame};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    cursor::Show,
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
-use crate::state;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use tokio::{sync::mpsc::{self, UnboundedReceiver}, time::{Interval, MissedTickBehavior}};
 
-use state::AppState;
+use crate::{cli::{Keymap, ThemePreset}, error::PortnordError, session::HistoryEntry, state};
+
+use state::{AppState, ErrorLog, FilterTarget, ForwardAllConfirmation, ForwardStatus, ForwardedPort, HistoryPicker, NamespacePicker, Overlay, Pane, PodPicker, Prompt, PromptKind, RoutePicker, ServiceInfo, ServiceKey, ServiceMeta, Severity, SortMode, StatusMessage};
 
 pub struct UI<'a> {
     pub terminal: ThisTerminal,
     pub app_state: &'a mut AppState,
+    pub keymap: Keymap,
+    theme: Theme,
+    /// Whether to use the compact layout whatever the size of the terminal
+    compact: bool,
+    /// Whether the keys changing the forwards are ignored, quitting always taking a second key
+    read_only: bool,
+    input: Input,
+    /// Advanced on each update to animate the loading spinner
+    spinner_frame: usize,
+    /// Bytes proxied by all the forwards when last drawn, to redraw as the counters move
+    drawn_traffic: u64,
+    /// When the UI was last drawn, to keep the uptimes of the forwards current
+    drawn_at: Instant,
+    panes: Panes,
+}
+
+/// Terminal events read on a thread of their own, for the forwards and the status updates not to wait on the terminal.
+struct Input {
+    events: UnboundedReceiver<io::Result<Event>>,
+    /// Ends the wait for input, to check for updates again
+    ticks: Interval,
+}
+
+impl Input {
+    fn spawn(tick_rate: Duration) -> Input {
+        let (sender, events) = mpsc::unbounded_channel();
+        thread::spawn(move || loop {
+            let result = event::read();
+            let failed = result.is_err();
+            // The UI is gone, or reading the terminal won't get any better
+            if sender.send(result).is_err() || failed {
+                break;
+            }
+        });
+        let mut ticks = tokio::time::interval(tick_rate);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Input { events, ticks }
+    }
+
+    /// Waits for the next event until the next tick, `None` once it's time to update.
+    async fn next(&mut self) -> Result<Option<Event>, PortnordError> {
+        tokio::select! {
+            event = self.events.recv() => match event {
+                Some(event) => event.map(Some).map_err(PortnordError::Terminal),
+                None => Err(PortnordError::Terminal(io::Error::new(io::ErrorKind::BrokenPipe, "terminal input closed"))),
+            },
+            _ = self.ticks.tick() => Ok(None),
+        }
+    }
+}
+
+/// Where the lists were last drawn, to map mouse clicks to their rows
+#[derive(Default)]
+struct Panes {
+    services: ListArea,
+    /// Rows of the services list, whose namespace headers the selection of `AppState` doesn't count
+    service_rows: ListState,
+    /// Index in the services list of the service on each row, `None` for headers
+    service_at_row: Vec<Option<usize>>,
+    ports: ListArea,
+    /// When and where the last click happened, to detect double-clicks
+    last_click: Option<(Instant, u16, u16)>,
+}
+
+/// Two clicks on the same row within this delay toggle forwarding
+const DOUBLE_CLICK_DELAY: Duration = Duration::from_millis(400);
+
+#[derive(Default)]
+struct ListArea {
+    area: Rect,
+    /// Index of the first visible row
+    offset: usize,
+}
+
+impl ListArea {
+    /// Mirrors how tui scrolls a list of single-line items to keep the selected one visible.
+    fn update(&mut self, area: Rect, len: usize, selected: Option<usize>) {
+        self.area = area;
+        let height = area.height.saturating_sub(2) as usize;
+        match selected {
+            Some(selected) if height > 0 && len > 0 => {
+                let selected = selected.min(len - 1);
+                let mut start = self.offset.min(len - 1);
+                if selected >= start + height {
+                    start = selected + 1 - height;
+                }
+                self.offset = start.min(selected);
+            }
+            _ => self.offset = 0,
+        }
+    }
+
+    /// Rows visible at once
+    fn page(&self) -> usize {
+        self.area.height.saturating_sub(2).max(1) as usize
+    }
+
+    /// Index of the item at the given position, if any.
+    fn index_at(&self, column: u16, row: u16, len: usize) -> Option<usize> {
+        // The rows are drawn within the borders
+        let inner = Rect::new(self.area.x + 1, self.area.y + 1, self.area.width.saturating_sub(2), self.area.height.saturating_sub(2));
+        let within = column >= inner.x && column < inner.x + inner.width && row >= inner.y && row < inner.y + inner.height;
+        if !within {
+            return None;
+        }
+        Some(self.offset + (row - inner.y) as usize).filter(|index| *index < len)
+    }
+}
+
+/// Styles of everything drawn with a color, picked from a preset with `--theme`
+#[derive(Clone, Copy)]
+struct Theme {
+    /// Selected row of a list
+    highlight: Style,
+    /// Border of the pane the arrows move in
+    focus: Style,
+    /// Keys in the key bindings and help
+    key: Style,
+    /// Namespace, pods and the part of a name matching the filter
+    accent: Style,
+    /// Secondary details, e.g. the traffic of a forward
+    muted: Style,
+    /// Forwards connecting and services loading
+    pending: Style,
+    active: Style,
+    info: Style,
+    warning: Style,
+    error: Style,
+}
+
+impl From<ThemePreset> for Theme {
+    fn from(preset: ThemePreset) -> Theme {
+        match preset {
+            ThemePreset::Dark => Theme {
+                highlight: Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                focus: Style::default().fg(Color::Yellow),
+                key: Style::default().fg(Color::Green),
+                accent: Style::default().fg(Color::Cyan),
+                muted: Style::default().fg(Color::DarkGray),
+                pending: Style::default().fg(Color::Yellow),
+                active: Style::default().fg(Color::Green),
+                info: Style::default().fg(Color::Cyan),
+                warning: Style::default().fg(Color::Yellow),
+                error: Style::default().fg(Color::Red),
+            },
+            // Yellow and cyan are hard to read on a light background
+            ThemePreset::Light => Theme {
+                highlight: Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+                focus: Style::default().fg(Color::Blue),
+                key: Style::default().fg(Color::Magenta),
+                accent: Style::default().fg(Color::Blue),
+                muted: Style::default().fg(Color::Gray),
+                pending: Style::default().fg(Color::Magenta),
+                active: Style::default().fg(Color::Green),
+                info: Style::default().fg(Color::Blue),
+                warning: Style::default().fg(Color::Magenta),
+                error: Style::default().fg(Color::Red),
+            },
+            // Modifiers only, for monochrome terminals
+            ThemePreset::Mono => Theme {
+                highlight: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+                focus: Style::default().add_modifier(Modifier::BOLD),
+                key: Style::default().add_modifier(Modifier::BOLD),
+                accent: Style::default().add_modifier(Modifier::BOLD),
+                muted: Style::default().add_modifier(Modifier::DIM),
+                pending: Style::default().add_modifier(Modifier::DIM),
+                active: Style::default().add_modifier(Modifier::BOLD),
+                info: Style::default(),
+                warning: Style::default().add_modifier(Modifier::BOLD),
+                error: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            },
+        }
+    }
+}
+
+/// How the panes are laid out, the compact layout making the most of small terminals
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LayoutMode {
+    Regular,
+    /// Single line footer and no details pane, leaving more rows to the lists
+    Compact,
+}
+
+/// Terminals narrower or shorter than this get the compact layout, e.g. 80x24
+const COMPACT_WIDTH: u16 = 100;
+const COMPACT_HEIGHT: u16 = 30;
+
+impl LayoutMode {
+    fn for_area(area: Rect, compact: bool) -> LayoutMode {
+        if compact || area.width < COMPACT_WIDTH || area.height < COMPACT_HEIGHT {
+            LayoutMode::Compact
+        } else {
+            LayoutMode::Regular
+        }
+    }
 }
 
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
 impl<'a> UI<'a> {
     
-    pub fn new(app_state: &'a mut AppState) -> UI<'a> {
+    pub fn new(app_state: &'a mut AppState, keymap: Keymap, theme: ThemePreset, compact: bool, read_only: bool, tick_rate: Duration) -> UI<'a> {
         let terminal: ThisTerminal = setup_terminal();
-        UI { terminal, app_state, }
+        UI { terminal, app_state, keymap, theme: Theme::from(theme), compact, read_only, input: Input::spawn(tick_rate), spinner_frame: 0, drawn_traffic: 0, drawn_at: Instant::now(), panes: Panes::default() }
     }
 
-    pub async fn update(&mut self) -> Result<bool, Box<dyn std::error::Error>>  {
+    pub async fn update(&mut self) -> Result<bool, PortnordError> {
+        self.app_state.update_messages();
+        self.app_state.update_credentials().await;
+        self.app_state.update_services().await;
+        self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
+        let spinner = SPINNER_FRAMES[self.spinner_frame];
+        self.app_state.update_bulk_forwards();
+        self.app_state.update_restarts().await;
+        self.app_state.update_forwards();
+        self.app_state.publish_forwards();
+        let traffic: u64 = self.app_state.forwarded_ports
+            .iter()
+            .map(|fw_port| fw_port.traffic.sent() + fw_port.traffic.received())
+            .sum();
+        // Nothing to redraw unless the state changed, the counters moved, the spinner is animating or an uptime ticked
+        let uptime_ticked = !self.app_state.forwarded_ports.is_empty() && self.drawn_at.elapsed() >= Duration::from_secs(1);
+        if !self.app_state.dirty && !self.app_state.loading && traffic == self.drawn_traffic && !uptime_ticked {
+            return handle_events(&mut self.terminal, self.app_state, self.keymap, self.read_only, &mut self.input, &mut self.panes).await;
+        }
+        self.app_state.dirty = false;
+        self.drawn_traffic = traffic;
+        self.drawn_at = Instant::now();
         self.terminal.draw(|f| {
+            let mut area = f.size();
+            // Stays up until the services can be listed again
+            if self.app_state.credentials_expired {
+                let banner_chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+                    .split(area);
+                f.render_widget(build_credentials_banner(&self.theme), banner_chunks[0]);
+                area = banner_chunks[1];
+            }
+            let layout = LayoutMode::for_area(area, self.compact);
+            let footer_constraint = match layout {
+                LayoutMode::Regular => Constraint::Percentage(10),
+                LayoutMode::Compact => Constraint::Length(1),
+            };
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
-                .constraints(
-                    [
-                        Constraint::Percentage(90),
-                        Constraint::Percentage(10)
-                    ]
-                    .as_ref(),
-                )
-                .split(f.size());
-            build_services(f, chunks[0], &mut self.app_state);
-            build_footer(f, chunks[1], &mut self.app_state);
-        }).unwrap();
+                .constraints([Constraint::Min(0), footer_constraint].as_ref())
+                .split(area);
+            build_services(f, chunks[0], self.app_state, spinner, &mut self.panes, layout, &self.theme);
+            match layout {
+                LayoutMode::Regular => build_footer(f, chunks[1], self.app_state, self.keymap, self.read_only, &self.theme),
+                LayoutMode::Compact => f.render_widget(build_compact_footer(self.app_state, self.keymap, self.read_only, &self.theme), chunks[1]),
+            }
+            if let Some(prompt) = &self.app_state.prompt {
+                build_prompt(f, prompt);
+            }
+            match &mut self.app_state.overlay {
+                Some(Overlay::Help) => build_help(f, self.keymap, &self.theme),
+                Some(Overlay::Pods(picker)) => build_pod_picker(f, picker, &self.theme),
+                Some(Overlay::Namespaces(picker)) => build_namespace_picker(f, picker, &self.theme),
+                Some(Overlay::Routes(picker)) => build_route_picker(f, picker, &self.theme),
+                Some(Overlay::ForwardAll(confirmation)) => build_forward_all_confirmation(f, confirmation, &self.theme),
+                Some(Overlay::ServiceInfo(info)) => {
+                    let service_meta = self.app_state.service_meta.get(&info.service);
+                    build_service_info(f, info, service_meta)
+                }
+                Some(Overlay::Errors(log)) => build_error_log(f, log, &self.app_state.errors, &self.theme),
+                Some(Overlay::History(picker)) => build_history_picker(f, picker, &self.app_state.history, &self.app_state.forwarded_ports, &self.theme),
+                None => (),
+            }
+            if self.app_state.pending_quit {
+                build_quit_confirmation(f, self.app_state.forwarded_ports.len(), &self.theme);
+            }
+        }).map_err(PortnordError::Terminal)?;
 
-        handle_events(&mut self.terminal, &mut self.app_state).await
+        handle_events(&mut self.terminal, self.app_state, self.keymap, self.read_only, &mut self.input, &mut self.panes).await
+    }
+
+    /// Cleans up like quitting with `q`, e.g. when interrupted.
+    pub async fn quit(&mut self) -> Result<bool, PortnordError> {
+        quit(&mut self.terminal, self.app_state).await
     }
 }
 
 type ThisTerminal = Terminal<CrosstermBackend<std::io::Stdout>>;
 
-fn command_list() -> BTreeMap<&'static str, &'static str> {
+fn move_keys(keymap: Keymap) -> &'static str {
+    match keymap {
+        Keymap::Default => "Arrows (←↑→↓)",
+        Keymap::Vim => "Arrows (←↑→↓) / hjkl",
+    }
+}
+
+/// Key bindings shown in the footer, see `command_groups` for all of them
+fn command_list(keymap: Keymap) -> BTreeMap<&'static str, &'static str> {
     BTreeMap::from([
-        ("Arrows (←↑→↓)", "Move around"),
+        (move_keys(keymap), "Move around"),
         ("Enter", "Toggle port forwarding"),
+        ("?", "Help"),
         ("q", "Quit"),
     ])
 }
 
-fn build_block(title: &str) -> Block {
+/// Abbreviated key bindings of the compact footer
+fn short_command_list(keymap: Keymap) -> [(&'static str, &'static str); 4] {
+    let move_keys = match keymap {
+        Keymap::Default => "←↑→↓",
+        Keymap::Vim => "hjkl",
+    };
+    [(move_keys, "move"), ("⏎", "toggle"), ("?", "help"), ("q", "quit")]
+}
+
+/// Every key binding, grouped by category
+fn command_groups(keymap: Keymap) -> Vec<(&'static str, Vec<(&'static str, &'static str)>)> {
+    vec![
+        ("Navigation", vec![
+            (move_keys(keymap), "Move around"),
+            ("Tab / Shift+Tab", "Move the focus to the other pane, keeping the selected port"),
+            ("PgUp / PgDn", "Move by a page"),
+            ("Home / End", "Jump to the first / last"),
+            ("] / [", "Jump to the next / previous service with forwards"),
+            ("/", "Filter services or ports"),
+            ("Esc", "Clear the filter"),
+            ("s", "Cycle the sort order"),
+            ("t", "Cycle the type of the services shown"),
+        ]),
+        ("Forwarding", vec![
+            ("Enter", "Toggle port forwarding"),
+            ("Space", "Mark a port to toggle with Enter"),
+            ("p", "Forward to a custom local port"),
+            ("o", "Forward to a specific pod"),
+            ("w", "Forward the service behind an OpenShift route"),
+            ("H", "Forward a port forwarded before again"),
+            ("r", "Forward a range of ports"),
+            ("R", "Restart the selected forward"),
+            ("X", "Stop all forwards"),
+            ("y", "Copy local address"),
+            ("c", "Copy the equivalent kubectl command"),
+            ("b", "Open an HTTP port in the browser, forwarding it first"),
+        ]),
+        ("General", vec![
+            ("i", "Show service labels/annotations"),
+            ("e", "Show the latest errors in full"),
+            ("n", "Switch namespace"),
+            ("F5", "Refresh the services now"),
+            ("?", "Show/hide this help"),
+            ("q", "Quit"),
+            ("Ctrl+C", "Quit without confirmation"),
+        ]),
+    ]
+}
+
+fn build_block<'a, T: Into<Cow<'a, str>>>(title: T) -> Block<'a> {
     Block::default()
             .borders(Borders::ALL)
             .title(Span::styled(
@@ -62,16 +379,26 @@ fn build_block(title: &str) -> Block {
             ))
 }
 
-fn build_key_bindings_paragraph<'a>() -> Paragraph<'a> {
-    let commands = command_list();
-    let command_spans: Vec<Span> = commands.into_iter().map(|command| {
+/// Block of the services or ports pane, its border highlighted when it has the focus.
+fn build_pane_block<'a, T: Into<Cow<'a, str>>>(title: T, focused: bool, theme: &Theme) -> Block<'a> {
+    let block = build_block(title);
+    if focused {
+        block.border_style(theme.focus)
+    } else {
+        block
+    }
+}
+
+fn build_key_bindings_paragraph<'a>(keymap: Keymap, read_only: bool, theme: &Theme) -> Paragraph<'a> {
+    let commands = command_list(keymap);
+    let command_spans: Vec<Span> = read_only_indicator(read_only, theme).into_iter().chain(commands.into_iter().flat_map(|command| {
         vec![
-            Span::styled(command.0.to_owned(), Style::default().fg(Color::Green)),    
+            Span::styled(command.0.to_owned(), theme.key),
             Span::styled(": ", Style::default().add_modifier(Modifier::BOLD)),
             Span::styled(command.1.to_owned(), Style::default().add_modifier(Modifier::ITALIC)),
             Span::raw("   "),
         ]
-    }).flatten().collect();
+    })).collect();
 
     Paragraph::new(Spans::from(command_spans))
         .block(build_block("Key bindings"))
@@ -79,11 +406,25 @@ fn build_key_bindings_paragraph<'a>() -> Paragraph<'a> {
         .wrap(tui::widgets::Wrap { trim: true })
 }
 
-fn build_namespace_paragraph<'a>(namespace_opt: Option<String>) -> Paragraph<'a> {
+/// Leads the key bindings while the forwards can't be changed.
+fn read_only_indicator<'a>(read_only: bool, theme: &Theme) -> Vec<Span<'a>> {
+    if read_only {
+        vec![Span::styled("READ-ONLY", theme.warning.add_modifier(Modifier::BOLD | Modifier::REVERSED)), Span::raw("   ")]
+    } else {
+        vec![]
+    }
+}
+
+/// Namespaces the services are listed from, the first two followed by how many others when listing more.
+fn build_namespace_paragraph<'a>(state: &AppState, theme: &Theme) -> Paragraph<'a> {
+    let namespace = match &state.namespaces[..] {
+        [first, second, others @ ..] if !state.all_namespaces && !others.is_empty() => format!("{}, {} +{}", first, second, others.len()),
+        _ => state.namespaces_label(),
+    };
     let namespace_spans = vec![
         Span::styled(
-            namespace_opt.unwrap_or("default".to_string()), 
-            Style::default().add_modifier(Modifier::BOLD | Modifier::ITALIC).fg(Color::Cyan)),
+            namespace, 
+            theme.accent.add_modifier(Modifier::BOLD | Modifier::ITALIC)),
     ];
     Paragraph::new(Spans::from(namespace_spans))
         .block(build_block("Namespace"))
@@ -91,39 +432,391 @@ fn build_namespace_paragraph<'a>(namespace_opt: Option<String>) -> Paragraph<'a>
         .wrap(tui::widgets::Wrap { trim: true})
 }
 
-fn build_services_list<'a>(services: &'a Vec<String>, forwarded_ports: &Vec<state::ForwardedPort>) -> List<'a> {
-    let items: Vec<ListItem> = services.iter().map(|service| 
-        ListItem::new(vec![Spans::from(Span::styled(
-            service,
-            if forwarded_ports.iter().find(|fw_port| &fw_port.service == service).is_some() {
-                Style::default().add_modifier(Modifier::ITALIC).add_modifier(Modifier::UNDERLINED)
-            } else {
-                Style::default().add_modifier(Modifier::ITALIC)
-            }
-        ))])
-    ).collect();
+fn build_credentials_banner<'a>(theme: &Theme) -> Paragraph<'a> {
+    let text = "Credentials expired or rejected, restart or renew them in the kube config: they are reloaded on each refresh";
+    Paragraph::new(Span::styled(text, theme.error.add_modifier(Modifier::BOLD | Modifier::REVERSED)))
+        .alignment(Alignment::Center)
+}
+
+/// Number of services listed and of forwards running.
+fn build_stats_paragraph<'a>(state: &AppState, theme: &Theme) -> Paragraph<'a> {
+    let stats_spans = vec![
+        Span::raw("services: "),
+        Span::styled(state.service_list().len().to_string(), theme.accent),
+        Span::raw("  forwarded: "),
+        Span::styled(state.forwarded_ports.len().to_string(), theme.accent),
+    ];
+    Paragraph::new(Spans::from(stats_spans))
+        .block(build_block("Stats"))
+        .alignment(Alignment::Left)
+        .wrap(tui::widgets::Wrap { trim: true })
+}
+
+/// A row of the services list: a namespace heading its services when listing several, or a service
+enum ServiceRow<'a> {
+    Namespace(&'a str),
+    Service(&'a ServiceKey),
+}
+
+/// Rows of `services`, each namespace preceded by a header when `grouped`; the services are expected grouped already.
+fn service_rows(services: &[ServiceKey], grouped: bool) -> Vec<ServiceRow<'_>> {
+    let mut rows = vec![];
+    for (index, service) in services.iter().enumerate() {
+        if grouped && (index == 0 || services[index - 1].namespace != service.namespace) {
+            rows.push(ServiceRow::Namespace(&service.namespace));
+        }
+        rows.push(ServiceRow::Service(service));
+    }
+    rows
+}
+
+fn build_services_list<'a>(rows: &[ServiceRow], state: &AppState, theme: &Theme) -> List<'a> {
+    let items: Vec<ListItem> = rows.iter().map(|row| {
+        let service = match row {
+            ServiceRow::Namespace(namespace) => return ListItem::new(Span::styled(namespace.to_string(), theme.muted.add_modifier(Modifier::BOLD))),
+            ServiceRow::Service(service) => service,
+        };
+        let ready_endpoints = state.ready_endpoints.get(service).copied();
+        let mut style = if state.forwarded_ports.iter().any(|fw_port| fw_port.is_for(service)) {
+            Style::default().add_modifier(Modifier::ITALIC).add_modifier(Modifier::UNDERLINED)
+        } else {
+            Style::default().add_modifier(Modifier::ITALIC)
+        };
+        // Forwarding a service without ready endpoints would fail
+        if ready_endpoints == Some(0) {
+            style = style.patch(theme.muted);
+        }
+        // Grouped services are indented under the header of their namespace
+        let label = if state.multiple_namespaces() { format!("  {}", service.name) } else { service.name.clone() };
+        let mut spans = build_filtered_spans(label, &state.service_filter, style, theme);
+        if let Some(ready) = ready_endpoints {
+            spans.0.push(Span::styled(format!(" ({} ready)", ready), theme.muted));
+        }
+        if state.service_meta.get(service).is_some_and(ServiceMeta::is_headless) {
+            spans.0.push(Span::styled(" (headless)", theme.muted));
+        }
+        if state.stale_services.contains(service) {
+            spans.0.push(Span::styled(" (stale)", theme.muted));
+        }
+        ListItem::new(vec![spans])
+    }).collect();
     List::new(items)
-        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
-        .block(build_block("Services"))
+        .highlight_style(theme.highlight)
+        .block(build_pane_block(services_title(state), state.focused_pane() == Pane::Services, theme))
 }
 
-fn build_ports_list<'a>(ports: &'a Vec<i32>, forwarded_ports: &Vec<&state::ForwardedPort>) -> List<'a> {
-    let items: Vec<ListItem> = ports.iter().map(|port| 
-        ListItem::new(vec![Spans::from(Span::styled(
-            port.to_string(),
-            if forwarded_ports.iter().find(|fw_port| fw_port.port == port.to_owned() as u16).is_some() {
-                Style::default().add_modifier(Modifier::ITALIC).add_modifier(Modifier::UNDERLINED)
-            } else {
-                Style::default().add_modifier(Modifier::ITALIC)
+/// Title of the services pane, showing the filter being typed and the sort order.
+fn services_title(state: &AppState) -> String {
+    let mut title = match &state.service_filter {
+        Some(filter) if state.filtering == Some(FilterTarget::Services) => format!("Services /{}_", filter),
+        Some(filter) => format!("Services /{}", filter),
+        None => "Services".to_string(),
+    };
+    if let Some(type_filter) = &state.type_filter {
+        title.push_str(&format!(" ({} only)", type_filter));
+    }
+    if state.sort_mode != SortMode::NameAscending {
+        title.push_str(&format!(" (by {})", state.sort_mode.label()));
+    }
+    title
+}
+
+fn build_loading_paragraph<'a>(spinner: &str, theme: &Theme) -> Paragraph<'a> {
+    Paragraph::new(Span::styled(format!("{} Loading services…", spinner), theme.pending))
+        .block(build_pane_block("Services", true, theme))
+}
+
+/// Explains why the services list is empty, vertically centered in `area`.
+fn build_no_services_paragraph<'a>(state: &AppState, area: Rect, theme: &Theme) -> Paragraph<'a> {
+    let text = match (&state.service_filter, &state.type_filter) {
+        (Some(filter), _) => format!("No services match /{}", filter),
+        (None, Some(type_filter)) => format!("No {} services listed", type_filter),
+        (None, None) if state.all_namespaces => "No services found in any namespace".to_string(),
+        (None, None) if state.multiple_namespaces() => format!("No services found in namespaces {}", state.namespaces_label()),
+        (None, None) => format!("No services found in namespace {}", state.namespace()),
+    };
+    let mut spans = vec![Spans::default(); (area.height.saturating_sub(3) / 2) as usize];
+    spans.push(Spans::from(Span::styled(text, Style::default().add_modifier(Modifier::ITALIC))));
+    Paragraph::new(spans)
+        .block(build_pane_block(services_title(state), true, theme))
+        .alignment(Alignment::Center)
+}
+
+/// Placeholder of the ports pane for a service without ports, vertically centered in `area`.
+fn build_no_ports_paragraph<'a>(area: Rect, theme: &Theme) -> Paragraph<'a> {
+    let mut spans = vec![Spans::default(); (area.height.saturating_sub(3) / 2) as usize];
+    spans.push(Spans::from(Span::styled("This service exposes no ports", theme.muted.add_modifier(Modifier::ITALIC))));
+    Paragraph::new(spans)
+        .block(build_pane_block("Ports", false, theme))
+        .alignment(Alignment::Center)
+}
+
+/// Human-readable duration down to the second, e.g. `3m12s` or `2h05m`.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, secs) => format!("{}s", secs),
+        (0, mins, secs) => format!("{}m{:02}s", mins, secs),
+        (hours, mins, _) => format!("{}h{:02}m", hours, mins),
+    }
+}
+
+/// Human-readable byte count, e.g. `1.2 MiB` or `340 KiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{:.1} {}", value, UNITS[unit])
+    } else {
+        format!("{:.0} {}", value, UNITS[unit])
+    }
+}
+
+/// Splits `text` so the part matching the filter is highlighted.
+fn build_filtered_spans<'a>(text: String, filter: &Option<String>, style: Style, theme: &Theme) -> Spans<'a> {
+    let match_start = filter
+        .as_ref()
+        .filter(|filter| !filter.is_empty())
+        .and_then(|filter| text.to_ascii_lowercase().find(&filter.to_ascii_lowercase()).map(|start| (start, filter.len())));
+    if let Some((start, len)) = match_start {
+        Spans::from(vec![
+            Span::styled(text[..start].to_owned(), style),
+            Span::styled(text[start..start + len].to_owned(), style.patch(theme.accent).add_modifier(Modifier::BOLD)),
+            Span::styled(text[start + len..].to_owned(), style),
+        ])
+    } else {
+        Spans::from(Span::styled(text, style))
+    }
+}
+
+fn build_ports_list<'a>(
+    ports: &[state::PortInfo],
+    forwarded_ports: &[&state::ForwardedPort],
+    marked_ports: &[i32],
+    title: String,
+    focused: bool,
+    theme: &Theme
+) -> List<'a> {
+    let items: Vec<ListItem> = ports.iter().map(|port| {
+        let mark_span = if marked_ports.contains(&port.port) {
+            Span::styled("✓ ", theme.accent.add_modifier(Modifier::BOLD))
+        } else {
+            Span::raw("  ")
+        };
+        let forwarded_port = forwarded_ports.iter().find(|fw_port| fw_port.port == port.port as u16);
+        let status_span = match forwarded_port.map(|fw_port| &fw_port.status) {
+            Some(ForwardStatus::Connecting) => Span::styled("● ", theme.pending),
+            Some(ForwardStatus::Active) => Span::styled("● ", theme.active),
+            Some(ForwardStatus::Failed(_)) => Span::styled("● ", theme.error),
+            None => Span::raw("  "),
+        };
+        let mut port_spans = vec![
+            mark_span,
+            status_span,
+            Span::styled(
+                match forwarded_port {
+                    Some(fw_port) if fw_port.local_port != fw_port.port => format!("{} -> {}", fw_port.local_port, port.label()),
+                    _ => port.label(),
+                },
+                if forwarded_port.is_some() {
+                    Style::default().add_modifier(Modifier::ITALIC).add_modifier(Modifier::UNDERLINED)
+                } else {
+                    Style::default().add_modifier(Modifier::ITALIC)
+                }
+            ),
+        ];
+        if !port.is_tcp() {
+            port_spans.push(Span::styled(format!(" {}", port.protocol), theme.muted));
+        }
+        if let Some(pod) = forwarded_port.and_then(|fw_port| fw_port.pod.as_ref()) {
+            port_spans.push(Span::styled(format!(" @ {}", pod), theme.accent));
+        }
+        if let Some(container_port) = forwarded_port.and_then(|fw_port| fw_port.container_port.as_ref()) {
+            port_spans.push(Span::styled(format!(" [{}]", container_port.label()), theme.accent));
+        }
+        if let Some(url) = forwarded_port.filter(|_| port.url_scheme().is_some()).map(|fw_port| fw_port.local_url(port)) {
+            port_spans.push(Span::styled(format!(" {}", url), theme.accent.add_modifier(Modifier::UNDERLINED)));
+        }
+        match forwarded_port.map(|fw_port| (&fw_port.status, &fw_port.traffic, fw_port.started_at)) {
+            Some((ForwardStatus::Failed(reason), _, _)) => {
+                port_spans.push(Span::styled(format!(" {}", reason), theme.error));
+            }
+            Some((_, traffic, started_at)) => {
+                let text = format!(
+                    " {} ↑ / {} ↓ · up {}", format_bytes(traffic.sent()), format_bytes(traffic.received()), format_duration(started_at.elapsed())
+                );
+                port_spans.push(Span::styled(text, theme.muted));
             }
-        ))])
-    ).collect();
+            None => (),
+        }
+        ListItem::new(vec![Spans::from(port_spans)])
+    }).collect();
     List::new(items)
-        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
-        .block(build_block("Ports"))
+        .highlight_style(theme.highlight)
+        .block(build_pane_block(title, focused, theme))
+}
+
+/// Title of the ports pane, showing the filter being typed and a non-loopback bind address.
+fn ports_title(state: &AppState) -> String {
+    let mut title = match &state.port_filter {
+        Some(filter) if state.filtering == Some(FilterTarget::Ports) => format!("Ports /{}_", filter),
+        Some(filter) => format!("Ports /{}", filter),
+        None => "Ports".to_string(),
+    };
+    // Marks may be spread across services
+    if !state.marked_ports.is_empty() {
+        title.push_str(&format!(" ({} marked)", state.marked_ports.len()));
+    }
+    if let Some(bulk_forward) = state.bulk_forward.as_ref().filter(|bulk_forward| state.service().as_ref() == Some(&bulk_forward.service)) {
+        title.push_str(&format!(" (forwarding {}/{}…)", bulk_forward.done, bulk_forward.total));
+    }
+    let bind_address = state.bind_address();
+    // Make it obvious that forwarded ports are reachable beyond this machine
+    if !bind_address.is_loopback() {
+        title.push_str(&format!(" (listening on {})", bind_address));
+    }
+    title
+}
+
+/// Single line footer of the compact layout: abbreviated key bindings, the namespace and the latest message, without borders.
+fn build_compact_footer<'a>(state: &AppState, keymap: Keymap, read_only: bool, theme: &Theme) -> Paragraph<'a> {
+    let mut footer_spans: Vec<Span> = read_only_indicator(read_only, theme).into_iter().chain(short_command_list(keymap).into_iter().flat_map(|(key, action)| {
+        vec![
+            Span::styled(key, theme.key),
+            Span::raw(format!(" {}  ", action)),
+        ]
+    })).collect();
+    footer_spans.push(Span::styled(state.namespaces_label(), theme.accent.add_modifier(Modifier::BOLD | Modifier::ITALIC)));
+    if let Some(message) = state.messages.last() {
+        let style = match message.severity {
+            Severity::Info => theme.info,
+            Severity::Warning => theme.warning,
+            Severity::Error => theme.error,
+        };
+        footer_spans.push(Span::raw("  "));
+        footer_spans.push(Span::styled(message.text.to_owned(), style));
+    }
+    Paragraph::new(Spans::from(footer_spans))
+}
+
+fn build_messages_paragraph<'a>(messages: &[StatusMessage], theme: &Theme) -> Paragraph<'a> {
+    let message_spans: Vec<Spans> = messages
+        .iter()
+        .rev()
+        .map(|message| {
+            let style = match message.severity {
+                Severity::Info => theme.info,
+                Severity::Warning => theme.warning,
+                Severity::Error => theme.error,
+            };
+            Spans::from(Span::styled(message.text.to_owned(), style))
+        })
+        .collect();
+
+    Paragraph::new(message_spans)
+        .block(build_block("Messages"))
+        .alignment(Alignment::Left)
+        .wrap(tui::widgets::Wrap { trim: true })
+}
+
+fn build_details_paragraph<'a>(state: &AppState) -> Paragraph<'a> {
+    let label_style = Style::default().add_modifier(Modifier::BOLD);
+    let details_spans = match state.service() {
+        Some(service) => {
+            let service_meta = &state.service_meta[&service];
+            let mut details_spans = vec![
+                Spans::from(vec![
+                    Span::styled("Type: ", label_style),
+                    Span::raw(service_meta.service_type.to_owned()),
+                ]),
+                Spans::from(vec![
+                    Span::styled("Cluster IP: ", label_style),
+                    Span::raw(match service_meta.cluster_ip.to_owned() {
+                        Some(cluster_ip) if service_meta.is_headless() => format!("{} (headless)", cluster_ip),
+                        cluster_ip => cluster_ip.unwrap_or("-".to_string()),
+                    }),
+                ]),
+                Spans::from(vec![
+                    Span::styled("Ports: ", label_style),
+                    Span::raw(state.port_list().len().to_string()),
+                ]),
+            ];
+            if let Some(selected_port) = state.port_selection.selected() {
+                let port = &state.port_list()[selected_port];
+                let target_port = match &port.target_port {
+                    Some(IntOrString::Int(port)) => port.to_string(),
+                    Some(IntOrString::String(name)) => name.to_owned(),
+                    None => "-".to_string(),
+                };
+                details_spans.push(Spans::from(vec![
+                    Span::styled("Target port: ", label_style),
+                    Span::raw(target_port),
+                ]));
+                details_spans.push(Spans::from(vec![
+                    Span::styled("Protocol: ", label_style),
+                    Span::raw(port.protocol.to_owned()),
+                ]));
+                let forwarded_ports = state.forwarded_ports_for_service(&service);
+                if let Some(fw_port) = forwarded_ports.iter().find(|fw_port| fw_port.port == port.port as u16) {
+                    let status = match &fw_port.status {
+                        ForwardStatus::Connecting => "Connecting".to_string(),
+                        ForwardStatus::Active => "Active".to_string(),
+                        ForwardStatus::Failed(reason) => format!("Failed ({})", reason),
+                    };
+                    details_spans.push(Spans::from(vec![
+                        Span::styled("Status: ", label_style),
+                        Span::raw(status),
+                    ]));
+                    details_spans.push(Spans::from(vec![
+                        Span::styled("Uptime: ", label_style),
+                        Span::raw(match fw_port.last_reconnect {
+                            Some(last_reconnect) => format!("{} (reconnected {} ago)", format_duration(fw_port.started_at.elapsed()), format_duration(last_reconnect.elapsed())),
+                            None => format_duration(fw_port.started_at.elapsed()),
+                        }),
+                    ]));
+                    details_spans.push(Spans::from(vec![
+                        Span::styled("Local address: ", label_style),
+                        Span::raw(fw_port.local_url(port)),
+                    ]));
+                    details_spans.push(Spans::from(vec![
+                        Span::styled("Pod: ", label_style),
+                        Span::raw(fw_port.pod_name.to_owned().unwrap_or_else(|| "-".to_string())),
+                    ]));
+                    details_spans.push(Spans::from(vec![
+                        Span::styled("Node: ", label_style),
+                        Span::raw(fw_port.node_name.to_owned().unwrap_or_else(|| "-".to_string())),
+                    ]));
+                    details_spans.push(Spans::from(vec![
+                        Span::styled("Target: ", label_style),
+                        Span::raw(match (&fw_port.pod, &fw_port.container_port) {
+                            (Some(pod), Some(container_port)) => format!("pod {}, container port {}", pod, container_port.label()),
+                            (Some(pod), None) => format!("pod {}", pod),
+                            (None, _) if service_meta.is_headless() => format!("pod {} (headless service)", fw_port.pod_name.as_deref().unwrap_or("-")),
+                            (None, _) => "service".to_string(),
+                        }),
+                    ]));
+                }
+            }
+            details_spans
+        }
+        None => vec![Spans::from(Span::styled("No service selected", Style::default().add_modifier(Modifier::ITALIC)))],
+    };
+
+    Paragraph::new(details_spans)
+        .block(build_block("Details"))
+        .alignment(Alignment::Left)
+        .wrap(tui::widgets::Wrap { trim: true })
 }
 
-fn build_services<B: Backend>(f: &mut Frame<B>, area: Rect, state: &mut AppState) {
+fn build_services<B: Backend>(f: &mut Frame<B>, area: Rect, state: &mut AppState, spinner: &str, panes: &mut Panes, layout: LayoutMode, theme: &Theme) {
     let chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints(
@@ -134,32 +827,322 @@ fn build_services<B: Backend>(f: &mut Frame<B>, area: Rect, state: &mut AppState
                     .as_ref(),
                 )
                 .split(area);           
-    f.render_stateful_widget(build_services_list(&state.service_list(), &state.forwarded_ports), chunks[0], &mut state.service_selection);
-    f.render_stateful_widget(build_ports_list(&state.port_list(), &state.forwarded_ports_for_selected_service()), chunks[1], &mut state.port_selection);
+    // The details are left out of the compact layout, the service info overlay showing the essentials
+    let side_constraints = match layout {
+        LayoutMode::Regular => [Constraint::Percentage(60), Constraint::Percentage(40)],
+        LayoutMode::Compact => [Constraint::Percentage(100), Constraint::Length(0)],
+    };
+    let side_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(side_constraints.as_ref())
+                .split(chunks[1]);
+    if state.loading {
+        f.render_widget(build_loading_paragraph(spinner, theme), chunks[0]);
+    } else if state.service_list().is_empty() {
+        f.render_widget(build_no_services_paragraph(state, chunks[0], theme), chunks[0]);
+    } else {
+        let service_list = state.service_list();
+        let rows = service_rows(&service_list, state.multiple_namespaces());
+        panes.service_at_row = rows
+            .iter()
+            .scan(0, |index, row| Some(match row {
+                ServiceRow::Namespace(_) => None,
+                ServiceRow::Service(_) => { *index += 1; Some(*index - 1) }
+            }))
+            .collect();
+        let selected_row = state.service_selection.selected().and_then(|selected| panes.service_at_row.iter().position(|index| *index == Some(selected)));
+        panes.services.update(chunks[0], rows.len(), selected_row);
+        panes.service_rows.select(selected_row);
+        f.render_stateful_widget(build_services_list(&rows, state, theme), chunks[0], &mut panes.service_rows);
+    }
+    panes.ports.update(side_chunks[0], state.port_list().len(), state.port_selection.selected());
+    if state.selected_service_exposes_no_ports() {
+        f.render_widget(build_no_ports_paragraph(side_chunks[0], theme), side_chunks[0]);
+    } else {
+        f.render_stateful_widget(build_ports_list(&state.port_list(), &state.forwarded_ports_for_selected_service(), &state.marked_ports_for_selected_service(), ports_title(state), state.focused_pane() == Pane::Ports, theme), side_chunks[0], &mut state.port_selection);
+    }
+    if layout == LayoutMode::Regular {
+        f.render_widget(build_details_paragraph(state), side_chunks[1]);
+    }
 }
 
-fn build_footer<B: Backend>(f: &mut Frame<B>, area: Rect, state: &mut AppState) {
+fn build_footer<B: Backend>(f: &mut Frame<B>, area: Rect, state: &mut AppState, keymap: Keymap, read_only: bool, theme: &Theme) {
     let chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints(
                     [
-                        Constraint::Percentage(90),
+                        Constraint::Percentage(45),
+                        Constraint::Percentage(35),
+                        Constraint::Percentage(10),
                         Constraint::Percentage(10)
                     ]
                     .as_ref(),
                 )
                 .split(area); 
-    f.render_widget(build_key_bindings_paragraph(), chunks[0]);
-    f.render_widget(build_namespace_paragraph(state.namespace_opt.to_owned()), chunks[1]);
+    f.render_widget(build_key_bindings_paragraph(keymap, read_only, theme), chunks[0]);
+    f.render_widget(build_messages_paragraph(&state.messages, theme), chunks[1]);
+    f.render_widget(build_namespace_paragraph(state, theme), chunks[2]);
+    f.render_widget(build_stats_paragraph(state, theme), chunks[3]);
+}
+
+fn build_prompt<B: Backend>(f: &mut Frame<B>, prompt: &Prompt) {
+    let title = match &prompt.kind {
+        PromptKind::LocalPort { service, port } => format!("Local port for {}:{}", service, port),
+        PromptKind::PortRange { service } => format!("Ports of {} to forward (START-END)", service),
+    };
+    let area = centered_rect(40, 3, f.size());
+    let prompt_spans = vec![
+        Span::raw(prompt.input.to_owned()),
+        Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+    ];
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(Spans::from(prompt_spans))
+            .block(build_block(&title))
+            .alignment(Alignment::Left),
+        area
+    );
+}
+
+fn build_pod_picker<B: Backend>(f: &mut Frame<B>, picker: &mut PodPicker, theme: &Theme) {
+    // Container ports are listed under their pod, which forwards the port of the service
+    let items: Vec<ListItem> = picker.pods
+        .iter()
+        .map(|choice| match &choice.container_port {
+            Some(container_port) => ListItem::new(Span::styled(format!("  └ container port {}", container_port.label()), theme.muted)),
+            None => ListItem::new(Span::styled(choice.pod.to_owned(), Style::default().add_modifier(Modifier::ITALIC))),
+        })
+        .collect();
+    let list = List::new(items)
+        .highlight_style(theme.highlight)
+        .block(build_block(format!("Forward {}:{} to pod", picker.service, picker.port)));
+    let area = centered_rect(64, picker.pods.len() as u16 + 2, f.size());
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut picker.selection);
+}
+
+fn build_route_picker<B: Backend>(f: &mut Frame<B>, picker: &mut RoutePicker, theme: &Theme) {
+    let items: Vec<ListItem> = picker.routes
+        .iter()
+        .map(|route| {
+            let target = match route.port {
+                Some(port) => format!(" → {}:{}", route.service, port),
+                None => format!(" → {} (port not listed)", route.service),
+            };
+            ListItem::new(Spans::from(vec![
+                Span::styled(route.name.to_owned(), Style::default().add_modifier(Modifier::ITALIC)),
+                Span::styled(route.host.as_ref().map(|host| format!(" ({})", host)).unwrap_or_default(), theme.muted),
+                Span::raw(target),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .highlight_style(theme.highlight)
+        .block(build_block("Forward the service of a route"));
+    let area = centered_rect(80, picker.routes.len() as u16 + 2, f.size());
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut picker.selection);
+}
+
+fn build_history_picker<B: Backend>(f: &mut Frame<B>, picker: &mut HistoryPicker, history: &VecDeque<HistoryEntry>, forwarded_ports: &[ForwardedPort], theme: &Theme) {
+    let items: Vec<ListItem> = history
+        .iter()
+        .map(|entry| {
+            let service = ServiceKey { namespace: entry.namespace.clone(), name: entry.service.clone() };
+            let forwarded = forwarded_ports
+                .iter()
+                .any(|fw_port| fw_port.is_for(&service) && fw_port.port == entry.remote_port && fw_port.local_port == entry.local_port);
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{}:{}", service, entry.remote_port), Style::default().add_modifier(Modifier::ITALIC)),
+                Span::raw(format!(" → {}", entry.local_port)),
+                Span::styled(if forwarded { " (forwarded)" } else { "" }, theme.muted),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .highlight_style(theme.highlight)
+        .block(build_block("Forward a port again"));
+    let area = centered_rect(60, history.len() as u16 + 2, f.size());
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut picker.selection);
+}
+
+fn build_namespace_picker<B: Backend>(f: &mut Frame<B>, picker: &mut NamespacePicker, theme: &Theme) {
+    let items: Vec<ListItem> = picker.namespaces
+        .iter()
+        .map(|namespace| ListItem::new(Span::styled(
+            namespace.clone().unwrap_or_else(|| "(all namespaces)".to_string()),
+            Style::default().add_modifier(Modifier::ITALIC)
+        )))
+        .collect();
+    let list = List::new(items)
+        .highlight_style(theme.highlight)
+        .block(build_block("Switch namespace"));
+    let area = centered_rect(40, picker.namespaces.len() as u16 + 2, f.size());
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut picker.selection);
+}
+
+fn build_help<B: Backend>(f: &mut Frame<B>, keymap: Keymap, theme: &Theme) {
+    let mut help_spans = vec![];
+    for (category, commands) in command_groups(keymap) {
+        if !help_spans.is_empty() {
+            help_spans.push(Spans::default());
+        }
+        help_spans.push(Spans::from(Span::styled(category, Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED))));
+        for (key, description) in commands {
+            help_spans.push(Spans::from(vec![
+                Span::styled(format!("{:>24}", key), theme.key),
+                Span::styled("  ", Style::default()),
+                Span::styled(description, Style::default().add_modifier(Modifier::ITALIC)),
+            ]));
+        }
+    }
+    let area = centered_rect(64, help_spans.len() as u16 + 2, f.size());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(help_spans)
+            .block(build_block("Help"))
+            .alignment(Alignment::Left),
+        area
+    );
+}
+
+fn build_quit_confirmation<B: Backend>(f: &mut Frame<B>, forwarded_ports_count: usize, theme: &Theme) {
+    let area = centered_rect(60, 3, f.size());
+    let lead = if forwarded_ports_count > 0 { format!("You have {} active forwards, press ", forwarded_ports_count) } else { "Press ".to_string() };
+    let confirmation_spans = vec![
+        Span::raw(lead),
+        Span::styled("q", theme.key),
+        Span::raw(" again or "),
+        Span::styled("y", theme.key),
+        Span::raw(" to quit"),
+    ];
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(Spans::from(confirmation_spans))
+            .block(build_block("Quit?"))
+            .alignment(Alignment::Center),
+        area
+    );
+}
+
+/// Truncates `text` to `width` characters, ending with an ellipsis when it was longer.
+fn truncate(text: String, width: usize) -> String {
+    if text.chars().count() <= width {
+        text
+    } else {
+        let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+fn build_service_info<B: Backend>(f: &mut Frame<B>, info: &mut ServiceInfo, service_meta: Option<&ServiceMeta>) {
+    let area = centered_rect(100, f.size().height.saturating_sub(4), f.size());
+    let width = area.width.saturating_sub(2) as usize;
+    let heading_style = Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut info_spans = vec![];
+    if let Some(service_meta) = service_meta {
+        for (heading, entries) in [("Labels", &service_meta.labels), ("Annotations", &service_meta.annotations)] {
+            if !info_spans.is_empty() {
+                info_spans.push(Spans::default());
+            }
+            info_spans.push(Spans::from(Span::styled(heading, heading_style)));
+            if entries.is_empty() {
+                info_spans.push(Spans::from(Span::styled("None", Style::default().add_modifier(Modifier::ITALIC))));
+            }
+            for (key, value) in entries {
+                let key = format!("{}: ", key);
+                let value = truncate(value.to_owned(), width.saturating_sub(key.chars().count()));
+                info_spans.push(Spans::from(vec![
+                    Span::styled(key, Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(value),
+                ]));
+            }
+        }
+    }
+    // Keep the last lines in view rather than scrolling past them
+    let visible_lines = area.height.saturating_sub(2);
+    info.scroll = info.scroll.min((info_spans.len() as u16).saturating_sub(visible_lines));
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(info_spans)
+            .block(build_block(format!("Service {}", info.service)))
+            .alignment(Alignment::Left)
+            .scroll((info.scroll, 0)),
+        area
+    );
+}
+
+/// Each error under how long ago it happened, the latest first, wrapped rather than truncated.
+fn build_error_log<B: Backend>(f: &mut Frame<B>, log: &mut ErrorLog, errors: &VecDeque<StatusMessage>, theme: &Theme) {
+    let area = centered_rect(100, f.size().height.saturating_sub(4), f.size());
+    let mut error_spans = vec![];
+    for error in errors.iter().rev() {
+        if !error_spans.is_empty() {
+            error_spans.push(Spans::default());
+        }
+        error_spans.push(Spans::from(Span::styled(format!("{} ago", format_duration(error.created_at.elapsed())), theme.muted)));
+        error_spans.push(Spans::from(Span::styled(error.text.to_owned(), theme.error)));
+    }
+    // Keep the last lines in view rather than scrolling past them, long errors taking several lines once wrapped
+    let width = area.width.saturating_sub(2).max(1) as usize;
+    let wrapped_lines: usize = error_spans.iter().map(|spans| spans.width().max(1).div_ceil(width)).sum();
+    let visible_lines = area.height.saturating_sub(2);
+    log.scroll = log.scroll.min((wrapped_lines as u16).saturating_sub(visible_lines));
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(error_spans)
+            .block(build_block(format!("Errors ({})", errors.len())))
+            .alignment(Alignment::Left)
+            .wrap(tui::widgets::Wrap { trim: false })
+            .scroll((log.scroll, 0)),
+        area
+    );
+}
+
+fn build_forward_all_confirmation<B: Backend>(f: &mut Frame<B>, confirmation: &ForwardAllConfirmation, theme: &Theme) {
+    let mut confirmation_spans = vec![
+        Spans::from(format!("Forward {} ports of service {} to:", confirmation.ports.len(), confirmation.service)),
+    ];
+    for local_address in confirmation.local_addresses() {
+        confirmation_spans.push(Spans::from(Span::styled(format!("  {}", local_address), theme.accent)));
+    }
+    confirmation_spans.push(Spans::default());
+    confirmation_spans.push(Spans::from(vec![
+        Span::raw("Press "),
+        Span::styled("Enter", theme.key),
+        Span::raw(" to confirm, any other key to cancel"),
+    ]));
+    let area = centered_rect(60, confirmation_spans.len() as u16 + 2, f.size());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(confirmation_spans)
+            .block(build_block("Forward all ports?"))
+            .alignment(Alignment::Left),
+        area
+    );
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height
+    )
 }
 
 fn setup_terminal() -> ThisTerminal {
+    install_panic_hook();
     enable_raw_mode().unwrap();
     let mut stdout = std::io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture).unwrap();
     let backend = CrosstermBackend::new(stdout);
-    let terminal = Terminal::new(backend).unwrap();
-    return terminal;
+    Terminal::new(backend).unwrap()
 }
 
 fn destroy_terminal(terminal: &mut ThisTerminal) {
@@ -172,40 +1155,396 @@ fn destroy_terminal(terminal: &mut ThisTerminal) {
     terminal.show_cursor().unwrap();
 }
 
-async fn handle_events<'a>(terminal: &mut ThisTerminal, state: &mut AppState) -> Result<bool, Box<dyn std::error::Error>> {
-    if crossterm::event::poll(Duration::from_millis(250))? {
-        if let Event::Key(key) = event::read()? {
-            match key.code {
-                KeyCode::Char('q') => {
-                    destroy_terminal(terminal);
-                    return Ok(false);
+/// Restores the terminal before the default panic handler prints its report,
+/// so a panic doesn't leave the shell in raw mode on the alternate screen.
+fn install_panic_hook() {
+    chain_panic_hook(|| {
+        let _ = disable_raw_mode();
+        let _ = execute!(std::io::stdout(), LeaveAlternateScreen, DisableMouseCapture, Show);
+    });
+}
+
+/// Runs `before` ahead of the panic hook installed so far, whatever thread panicked.
+fn chain_panic_hook(before: impl Fn() + Send + Sync + 'static) {
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |panic_info| {
+        before();
+        previous_hook(panic_info);
+    }));
+}
+
+/// Saves the session for `--restore`, restores the terminal, then stops the forwards, waiting up to the shutdown timeout for their requests in flight to complete.
+async fn quit(terminal: &mut ThisTerminal, state: &mut AppState) -> Result<bool, PortnordError> {
+    let save_result = state.save_session();
+    destroy_terminal(terminal);
+    state.shutdown().await;
+    save_result.map_err(PortnordError::Session)?;
+    Ok(false)
+}
+
+async fn handle_events(
+    terminal: &mut ThisTerminal,
+    state: &mut AppState,
+    keymap: Keymap,
+    read_only: bool,
+    input: &mut Input,
+    panes: &mut Panes
+) -> Result<bool, PortnordError> {
+    if let Some(event) = input.next().await? {
+        // Keys may change about anything, and a resize needs a full redraw
+        state.dirty = true;
+        if let Event::Key(key) = event {
+            // The terminal being in raw mode, Ctrl+C comes as a key rather than as a signal
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return quit(terminal, state).await
+            }
+            if state.pending_quit {
+                state.pending_quit = false;
+                if let KeyCode::Char('q' | 'y') = key.code {
+                    return quit(terminal, state).await
+                }
+                return Ok(true)
+            }
+            match state.overlay {
+                Some(Overlay::Help) => {
+                    if let KeyCode::Char('?') | KeyCode::Esc = key.code {
+                        state.overlay = None;
+                    }
+                    return Ok(true)
+                }
+                Some(Overlay::Pods(_)) => {
+                    handle_pod_picker_events(key.code, state, keymap).await;
+                    return Ok(true)
+                }
+                Some(Overlay::Routes(_)) => {
+                    handle_route_picker_events(key.code, state, keymap).await;
+                    return Ok(true)
+                }
+                Some(Overlay::History(_)) => {
+                    handle_history_picker_events(key.code, state, keymap).await;
+                    return Ok(true)
+                }
+                Some(Overlay::Namespaces(_)) => {
+                    handle_namespace_picker_events(key.code, state, keymap);
+                    return Ok(true)
+                }
+                Some(Overlay::ServiceInfo(_)) => {
+                    handle_service_info_events(key.code, state, keymap);
+                    return Ok(true)
+                }
+                Some(Overlay::Errors(_)) => {
+                    handle_error_log_events(key.code, state, keymap);
+                    return Ok(true)
+                }
+                Some(Overlay::ForwardAll(_)) => {
+                    if key.code == KeyCode::Enter {
+                        state.confirm_forward_all().await;
+                    } else {
+                        state.overlay = None;
+                    }
+                    return Ok(true)
+                }
+                None => (),
+            }
+            if state.prompt.is_some() {
+                handle_prompt_events(key.code, state).await;
+                return Ok(true)
+            }
+            if state.filtering.is_some() {
+                handle_filter_events(key.code, state);
+                return Ok(true)
+            }
+            let key_code = match (keymap, key.code) {
+                (Keymap::Vim, KeyCode::Char('h')) => KeyCode::Left,
+                (Keymap::Vim, KeyCode::Char('j')) => KeyCode::Down,
+                (Keymap::Vim, KeyCode::Char('k')) => KeyCode::Up,
+                (Keymap::Vim, KeyCode::Char('l')) => KeyCode::Right,
+                (_, key_code) => key_code,
+            };
+            if read_only && changes_forwards(key_code) {
+                state.push_message(StatusMessage::info("Read-only mode, the forwards can't be changed"));
+                return Ok(true)
+            }
+            match key_code {
+                KeyCode::Char('q') if read_only || !state.forwarded_ports.is_empty() => {
+                    state.pending_quit = true;
+                    Ok(true)
                 },
+                KeyCode::Char('q') => quit(terminal, state).await,
                 KeyCode::Enter => {
-                    state.toggle_port_forwarding().await?;
-                    return Ok(true)
+                    state.toggle_port_forwarding().await;
+                    Ok(true)
+                },
+                KeyCode::Char('r') => {
+                    state.open_port_range_prompt();
+                    Ok(true)
+                },
+                KeyCode::Char(' ') => {
+                    state.toggle_mark();
+                    Ok(true)
+                },
+                KeyCode::Char('p') => {
+                    state.open_local_port_prompt();
+                    Ok(true)
+                },
+                KeyCode::Char('y') => {
+                    state.copy_selected_address();
+                    Ok(true)
+                },
+                KeyCode::Char('c') => {
+                    state.copy_selected_kubectl_command();
+                    Ok(true)
+                },
+                KeyCode::Char('b') => {
+                    state.open_selected_in_browser().await;
+                    Ok(true)
+                },
+                KeyCode::Char('n') => {
+                    state.open_namespace_picker().await;
+                    Ok(true)
+                },
+                KeyCode::Char('i') => {
+                    state.open_service_info();
+                    Ok(true)
+                },
+                KeyCode::Char('e') => {
+                    state.open_error_log();
+                    Ok(true)
+                },
+                KeyCode::Char('X') => {
+                    state.stop_all_forwards().await;
+                    Ok(true)
+                },
+                KeyCode::Char('R') => {
+                    state.restart_selected_forward().await;
+                    Ok(true)
+                },
+                KeyCode::Char('o') => {
+                    state.open_pod_picker().await;
+                    Ok(true)
+                },
+                KeyCode::Char('w') => {
+                    state.open_route_picker().await;
+                    Ok(true)
+                },
+                KeyCode::Char('H') => {
+                    state.open_history();
+                    Ok(true)
+                },
+                KeyCode::Char('/') => {
+                    state.start_filter();
+                    Ok(true)
+                },
+                KeyCode::Char(']') => {
+                    state.select_next_forwarded_service();
+                    Ok(true)
+                },
+                KeyCode::Char('[') => {
+                    state.select_previous_forwarded_service();
+                    Ok(true)
+                },
+                KeyCode::Char('s') => {
+                    state.cycle_sort_mode();
+                    Ok(true)
+                },
+                KeyCode::F(5) => {
+                    state.refresh_services();
+                    Ok(true)
+                },
+                KeyCode::Char('t') => {
+                    state.cycle_type_filter();
+                    Ok(true)
+                },
+                KeyCode::Char('?') => {
+                    state.overlay = Some(Overlay::Help);
+                    Ok(true)
+                },
+                KeyCode::Esc => {
+                    state.clear_filter();
+                    Ok(true)
                 },
                 KeyCode::Left => {
                     state.deselect();
-                    return Ok(true)
+                    Ok(true)
+                },
+                KeyCode::PageDown | KeyCode::PageUp => {
+                    let list_area = if state.focused_pane() == Pane::Ports { &panes.ports } else { &panes.services };
+                    if key_code == KeyCode::PageDown {
+                        state.page_down(list_area.page());
+                    } else {
+                        state.page_up(list_area.page());
+                    }
+                    Ok(true)
+                },
+                KeyCode::Home => {
+                    state.select_first();
+                    Ok(true)
+                },
+                KeyCode::End => {
+                    state.select_last();
+                    Ok(true)
+                },
+                KeyCode::Tab | KeyCode::BackTab => {
+                    state.switch_focus();
+                    Ok(true)
                 },
                 KeyCode::Right => {
                     state.select();
-                    return Ok(true)
+                    Ok(true)
                 },
                 KeyCode::Down => {
                     state.next();
-                    return Ok(true)
+                    Ok(true)
                 },
                 KeyCode::Up => {
                     state.previous();
-                    return Ok(true)
+                    Ok(true)
                 },
-                _ => return Ok(true)
+                _ => Ok(true)
             }
+        } else if let Event::Mouse(mouse) = event {
+            handle_mouse_events(mouse, state, read_only, panes).await;
+            Ok(true)
+        } else if let Event::Resize(..) = event {
+            // The next draw lays the panes out against the new size, moving the rows a click was on
+            panes.last_click = None;
+            Ok(true)
         } else {
-            return Ok(true)
+            Ok(true)
         }
     } else {
-        return Ok(true)
+        Ok(true)
+    }
+}
+
+/// Selects the service or port clicked, a double-click toggling its forwarding unless read-only.
+async fn handle_mouse_events(mouse: MouseEvent, state: &mut AppState, read_only: bool, panes: &mut Panes) {
+    let modal_open = state.pending_quit || state.overlay.is_some() || state.prompt.is_some() || state.filtering.is_some();
+    if modal_open || mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+        return
+    }
+    let (column, row) = (mouse.column, mouse.row);
+    let double_click = panes.last_click
+        .is_some_and(|(clicked_at, last_column, last_row)| clicked_at.elapsed() < DOUBLE_CLICK_DELAY && (last_column, last_row) == (column, row));
+    panes.last_click = if double_click { None } else { Some((Instant::now(), column, row)) };
+    let service_index = panes.services.index_at(column, row, panes.service_at_row.len()).and_then(|row| panes.service_at_row[row]);
+    if let Some(index) = service_index {
+        state.select_service(index);
+    } else if let Some(index) = panes.ports.index_at(column, row, state.port_list().len()) {
+        state.select_port(index);
+    } else {
+        return
+    }
+    if double_click && !read_only {
+        state.toggle_port_forwarding().await;
     }
-}
\ No newline at end of file
+}
+
+/// Keys starting, stopping or restarting forwards, or opening what does, ignored in read-only mode.
+fn changes_forwards(key_code: KeyCode) -> bool {
+    matches!(key_code, KeyCode::Enter | KeyCode::Char(' ' | 'r' | 'p' | 'o' | 'w' | 'H' | 'b' | 'R' | 'X'))
+}
+
+fn handle_filter_events(key_code: KeyCode, state: &mut AppState) {
+    match key_code {
+        KeyCode::Enter => state.confirm_filter(),
+        KeyCode::Esc => state.clear_filter(),
+        KeyCode::Backspace => state.pop_filter_char(),
+        KeyCode::Char(c) => state.push_filter_char(c),
+        _ => ()
+    }
+}
+
+async fn handle_pod_picker_events(key_code: KeyCode, state: &mut AppState, keymap: Keymap) {
+    match (keymap, key_code) {
+        (_, KeyCode::Enter) => state.forward_to_selected_pod().await,
+        (_, KeyCode::Esc) => state.overlay = None,
+        (_, KeyCode::Down) | (Keymap::Vim, KeyCode::Char('j')) => state.next_pod(),
+        (_, KeyCode::Up) | (Keymap::Vim, KeyCode::Char('k')) => state.previous_pod(),
+        _ => ()
+    }
+}
+
+async fn handle_route_picker_events(key_code: KeyCode, state: &mut AppState, keymap: Keymap) {
+    match (keymap, key_code) {
+        (_, KeyCode::Enter) => state.forward_selected_route().await,
+        (_, KeyCode::Esc) => state.overlay = None,
+        (_, KeyCode::Down) | (Keymap::Vim, KeyCode::Char('j')) => state.next_route(),
+        (_, KeyCode::Up) | (Keymap::Vim, KeyCode::Char('k')) => state.previous_route(),
+        _ => ()
+    }
+}
+
+async fn handle_history_picker_events(key_code: KeyCode, state: &mut AppState, keymap: Keymap) {
+    match (keymap, key_code) {
+        (_, KeyCode::Enter) => state.forward_selected_history_entry().await,
+        (_, KeyCode::Esc) => state.overlay = None,
+        (_, KeyCode::Down) | (Keymap::Vim, KeyCode::Char('j')) => state.next_history_entry(),
+        (_, KeyCode::Up) | (Keymap::Vim, KeyCode::Char('k')) => state.previous_history_entry(),
+        _ => ()
+    }
+}
+
+fn handle_namespace_picker_events(key_code: KeyCode, state: &mut AppState, keymap: Keymap) {
+    match (keymap, key_code) {
+        (_, KeyCode::Enter) => state.switch_to_selected_namespace(),
+        (_, KeyCode::Esc) => state.overlay = None,
+        (_, KeyCode::Down) | (Keymap::Vim, KeyCode::Char('j')) => state.next_namespace(),
+        (_, KeyCode::Up) | (Keymap::Vim, KeyCode::Char('k')) => state.previous_namespace(),
+        _ => ()
+    }
+}
+
+fn handle_service_info_events(key_code: KeyCode, state: &mut AppState, keymap: Keymap) {
+    match (keymap, key_code) {
+        (_, KeyCode::Esc | KeyCode::Char('i')) => state.overlay = None,
+        (_, KeyCode::Down) | (Keymap::Vim, KeyCode::Char('j')) => state.scroll_service_info_down(),
+        (_, KeyCode::Up) | (Keymap::Vim, KeyCode::Char('k')) => state.scroll_service_info_up(),
+        _ => ()
+    }
+}
+
+fn handle_error_log_events(key_code: KeyCode, state: &mut AppState, keymap: Keymap) {
+    match (keymap, key_code) {
+        (_, KeyCode::Esc | KeyCode::Char('e')) => state.overlay = None,
+        (_, KeyCode::Down) | (Keymap::Vim, KeyCode::Char('j')) => state.scroll_error_log_down(),
+        (_, KeyCode::Up) | (Keymap::Vim, KeyCode::Char('k')) => state.scroll_error_log_up(),
+        _ => ()
+    }
+}
+
+async fn handle_prompt_events(key_code: KeyCode, state: &mut AppState) {
+    match key_code {
+        KeyCode::Enter => state.submit_prompt().await,
+        KeyCode::Esc => state.close_prompt(),
+        KeyCode::Backspace => {
+            if let Some(prompt) = &mut state.prompt {
+                prompt.input.pop();
+            }
+        },
+        KeyCode::Char(c) => {
+            if let Some(prompt) = &mut state.prompt {
+                if prompt.kind.accepts(c) {
+                    prompt.input.push(c);
+                }
+            }
+        },
+        _ => ()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn panic_hook_runs_for_a_panic_in_another_thread() {
+        static RAN: AtomicBool = AtomicBool::new(false);
+        chain_panic_hook(|| RAN.store(true, Ordering::SeqCst));
+        let result = std::thread::spawn(|| panic!("panicking on purpose")).join();
+        assert!(result.is_err());
+        assert!(RAN.load(Ordering::SeqCst));
+    }
+}